@@ -0,0 +1 @@
+au BufRead,BufNewFile *.ay set filetype=aysinvi
@@ -0,0 +1,33 @@
+" Vim syntax file
+" Language: Aysìnvi
+" Generated by `aysinvi syntax vim`, do not edit by hand
+
+if exists("b:current_syntax")
+  finish
+endif
+
+" Letters, digits, ', ì and ä
+syn iskeyword @,48-57,39,236,228
+
+syn keyword aysinviDeclaratorKeyword ngop 'u meu pxeu ayu alu txew lì'ukìng fmetok sar
+syn keyword aysinviControlKeyword txo tsakrr txokefyaw leyn vaykrr kem ftang
+syn keyword aysinviSeparatorKeyword fa si sì ulte ke may' livu
+syn keyword aysinviNumberKeyword melo pxelo teng vezeykoyu
+
+syn match aysinviNumber "\<[0-7]\+\>"
+syn match aysinviCall "\<[a-zìä']\+\(\.[a-zìä']\+\)\=\ze\s\+\(fa\|si\)\>"
+syn match aysinviDeclaration "\<\(lì'ukìng\|fmetok\|'u\|meu\|pxeu\|ayu\)\s\+\zs[a-zìä']\+\(\.[a-zìä']\+\)\="
+syn region aysinviString start="\<san\>" end="\s\zssìk"
+syn match aysinviComment "''.\{-}\(''\|$\)"
+
+hi def link aysinviDeclaratorKeyword Keyword
+hi def link aysinviControlKeyword Conditional
+hi def link aysinviSeparatorKeyword Operator
+hi def link aysinviNumberKeyword Constant
+hi def link aysinviNumber Number
+hi def link aysinviCall Function
+hi def link aysinviDeclaration Identifier
+hi def link aysinviString String
+hi def link aysinviComment Comment
+
+let b:current_syntax = "aysinvi"
@@ -0,0 +1,22 @@
+#![no_main]
+
+use aysinvi::ast::generate::{self, Generator};
+
+use libfuzzer_sys::fuzz_target;
+
+// Parsing, binding and typing may reject the input but never panic
+fuzz_target!(|data: &[u8]| {
+    // Raw input, mostly stopped by the grammar
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = generate::front_end(source);
+    }
+
+    // Programs following the grammar, seeded by the input to get past it
+    let mut seed = [0; 8];
+    seed.iter_mut().zip(data).for_each(|(seed, byte)| *seed = *byte);
+    let mut generator = Generator::new(u64::from_le_bytes(seed));
+
+    let program = generator.program();
+    let _ = generate::front_end(&program);
+    let _ = generate::front_end(&generator.mutate(&program));
+});
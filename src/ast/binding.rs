@@ -1,39 +1,59 @@
 use crate::{
     ast::{
-        lib::{convert_iter, wrap_scope, AyNode, ComparisonOperator, Multiplier, Node},
+        lib::{convert_iter, wrap_scope, AyNode, ComparisonOperator, Node, SourceFile},
         parsing::{Expr as PExpr, Statement as PStatement},
+        pattern::Term,
     },
     error::{
+        kind::{BindingError, ErrorKind},
+        lint::{Lint, Warnings},
+        locale::message,
         span::Span,
         trace::{Stage, Trace, TraceError},
-        trace_error::Error,
+        trace_error::{Error, Suggestion},
     },
+    interpreter::builtins::BUILTINS,
 };
 
-use {pest::error::LineColLocation, quickscope::ScopeMap};
+use {
+    quickscope::ScopeMap,
+    serde::Serialize,
+    strum_macros::{AsRefStr, EnumString},
+};
 
-#[derive(PartialEq, Eq, Default, Debug, Clone)]
+#[derive(PartialEq, Eq, Default, Debug, Clone, Serialize)]
 pub struct FunDec {
-    name: String,
-    args: Vec<String>,
-    body: Vec<AyNode<Statement>>,
+    pub name: String,
+    pub args: Vec<String>,
+    pub body: Vec<AyNode<Statement>>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, EnumString, AsRefStr)]
+#[strum(serialize_all = "lowercase")]
 pub enum Tense {
     Present,
     Imminent,
     Future,
 }
 
-#[derive(PartialEq, Eq, Debug, Default, Clone)]
+impl std::fmt::Display for Tense {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Tense::Present => "present",
+            Tense::Imminent => "imminent future",
+            Tense::Future => "future",
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Default, Clone, Serialize)]
 pub struct VarDec {
-    names: Vec<String>,
-    values: Vec<AyNode<Expr>>,
+    pub names: Vec<String>,
+    pub values: Vec<AyNode<Expr>>,
 }
 
 /// A statement is anything that cannot be expected to return a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub enum Statement {
     FunDec(FunDec),
     VarDec(VarDec),
@@ -47,11 +67,108 @@ pub enum Statement {
         cond: Option<AyNode<Expr>>,
         body: Vec<AyNode<Statement>>,
     },
+    Test {
+        name: String,
+        body: Vec<AyNode<Statement>>,
+    },
+}
+
+/// Tags: the same as the parsed AST's, `Fd(name, args, body)`, `Vd(names, values)`,
+/// `If(cond, then, otherwise)`, `Lp([cond], body)` and `Ts(name, body)`
+impl Node for Statement {
+    fn term(&self) -> Term {
+        match self {
+            Statement::FunDec(FunDec { name, args, body }) => Term::node(
+                "Fd",
+                vec![Term::leaf(name), Term::leaves(args), Term::list(body)],
+            ),
+            Statement::VarDec(VarDec { names, values }) => {
+                Term::node("Vd", vec![Term::leaves(names), Term::list(values)])
+            }
+            Statement::Expr(expr) => expr.term(),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => Term::node(
+                "If",
+                vec![cond.term(), Term::list(then), Term::list(otherwise)],
+            ),
+            Statement::Loop { cond, body } => {
+                Term::node("Lp", vec![Term::list(cond), Term::list(body)])
+            }
+            Statement::Test { name, body } => {
+                Term::node("Ts", vec![Term::leaf(name), Term::list(body)])
+            }
+        }
+    }
+
+    fn from_term(term: &Term, span: &Span) -> Option<Self> {
+        let Term::Node { tag, fields, .. } = term else {
+            return None;
+        };
+
+        Some(match (tag.as_str(), &fields[..]) {
+            ("Fd", [name, args, body]) => Statement::FunDec(FunDec {
+                name: name.as_leaf()?.to_owned(),
+                args: args.as_leaves()?,
+                body: body.as_nodes(span)?,
+            }),
+            ("Vd", [names, values]) => {
+                let (names, values) = (names.as_leaves()?, values.as_nodes(span)?);
+                if names.len() != values.len() {
+                    return None;
+                }
+                Statement::VarDec(VarDec { names, values })
+            }
+            ("If", [cond, then, otherwise]) => Statement::If {
+                cond: AyNode::from_term(cond, span)?,
+                then: then.as_nodes(span)?,
+                otherwise: otherwise.as_nodes(span)?,
+            },
+            ("Lp", [cond, body]) => Statement::Loop {
+                cond: match &cond.as_nodes(span)?[..] {
+                    [] => None,
+                    [cond] => Some(cond.clone()),
+                    _ => return None,
+                },
+                body: body.as_nodes(span)?,
+            },
+            ("Ts", [name, body]) => Statement::Test {
+                name: name.as_leaf()?.to_owned(),
+                body: body.as_nodes(span)?,
+            },
+            _ => Statement::Expr(AyNode::from_term(term, span)?),
+        })
+    }
+
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        match self {
+            Statement::FunDec(FunDec { body, .. })
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec(_) | Statement::Expr(_) => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        match self {
+            Statement::FunDec(FunDec { body, .. })
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec(_) | Statement::Expr(_) => vec![],
+        }
+    }
 }
-impl Node for Statement {}
 
 /// An expression is anything that is or returns a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub enum Expr {
     FunCall {
         tense: Tense,
@@ -70,38 +187,375 @@ pub enum Expr {
     String(String),
     Var(String),
     Negated(Box<AyNode<Expr>>),
+    /// Placeholder for an expression that failed to bind, so that binding can go on
+    Error,
+}
+
+/// Tags: the parsed AST's ones, calls having their tense as a third field, `Fc(name, args,
+/// tense)`, present unless given, along with `Er()` for errors
+impl Node for Expr {
+    fn term(&self) -> Term {
+        match self {
+            Expr::FunCall { tense, name, args } => Term::node(
+                "Fc",
+                vec![
+                    Term::leaf(name),
+                    Term::list(args),
+                    Term::leaf(tense.as_ref()),
+                ],
+            ),
+            Expr::Array { items } => Term::node("Ar", vec![Term::list(items)]),
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => Term::node(
+                "Cp",
+                vec![left.term(), right.term(), Term::leaf(operator.as_ref())],
+            ),
+            Expr::Number(number) => Term::node("Nb", vec![Term::leaf(number)]),
+            Expr::String(string) => Term::node("St", vec![Term::leaf(string)]),
+            Expr::Var(name) => Term::node("Id", vec![Term::leaf(name)]),
+            Expr::Negated(expr) => Term::node("Ng", vec![expr.term()]),
+            Expr::Error => Term::node("Er", vec![]),
+        }
+    }
+
+    fn from_term(term: &Term, span: &Span) -> Option<Self> {
+        let Term::Node { tag, fields, .. } = term else {
+            return None;
+        };
+
+        Some(match (tag.as_str(), &fields[..]) {
+            ("Fc", [name, args, tense @ ..]) if tense.len() <= 1 => Expr::FunCall {
+                tense: match tense {
+                    [tense] => tense.as_leaf()?.parse().ok()?,
+                    _ => Tense::Present,
+                },
+                name: name.as_leaf()?.to_owned(),
+                args: args.as_nodes(span)?,
+            },
+            ("Ar", [items]) => Expr::Array {
+                items: items.as_nodes(span)?,
+            },
+            ("Cp", [left, right, operator]) => Expr::Comparison {
+                left: Box::new(AyNode::from_term(left, span)?),
+                right: Box::new(AyNode::from_term(right, span)?),
+                operator: operator.as_leaf()?.parse().ok()?,
+            },
+            ("Nb", [number]) => Expr::Number(number.as_leaf()?.parse().ok()?),
+            ("St", [string]) => Expr::String(string.as_leaf()?.to_owned()),
+            ("Id", [name]) => Expr::Var(name.as_leaf()?.to_owned()),
+            ("Ng", [expr]) => Expr::Negated(Box::new(AyNode::from_term(expr, span)?)),
+            ("Er", []) => Expr::Error,
+            _ => return None,
+        })
+    }
+
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        match self {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => vec![items],
+            _ => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        match self {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => vec![items],
+            _ => vec![],
+        }
+    }
+}
+
+/// Where a name occurs in the source
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Location {
+    pub file: Option<String>,
+    pub span: Span,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SymbolKind {
+    Variable,
+    /// Argument of a function
+    Parameter,
+    Function,
+}
+
+/// Occurrence of a name, linked to the declaration it resolves to
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Reference {
+    pub kind: SymbolKind,
+    /// Name as declared, e.g. `t.aron` for a call to `tìyaron`
+    pub name: String,
+    pub location: Location,
+    /// `None` for builtins
+    pub declaration: Option<Location>,
+    /// Tense of function calls
+    pub tense: Option<Tense>,
+}
+
+impl Reference {
+    pub fn is_declaration(&self) -> bool {
+        self.declaration.as_ref() == Some(&self.location)
+    }
+}
+
+/// Every name occurrence met while binding, declarations included
+#[derive(Debug, Default, Clone)]
+pub struct References {
+    /// File currently being bound
+    pub file: Option<String>,
+    pub list: Vec<Reference>,
+}
+
+impl References {
+    fn location(&self, span: &Span, name: &str) -> Location {
+        Location {
+            file: self.file.clone(),
+            span: span.find_word(name).unwrap_or_else(|| span.clone()),
+        }
+    }
+
+    fn declare(&mut self, kind: SymbolKind, span: &Span, name: &str) -> Location {
+        let location = self.location(span, name);
+
+        self.list.push(Reference {
+            kind,
+            name: name.to_owned(),
+            location: location.clone(),
+            declaration: Some(location.clone()),
+            tense: None,
+        });
+
+        location
+    }
+}
+
+/// Names visible to the binder, kept alive between inputs by the REPL
+#[derive(Clone)]
+pub struct Scopes {
+    pub vars: ScopeMap<String, (SymbolKind, Location)>,
+    /// Builtins have no declaration
+    pub funs: ScopeMap<String, Option<Location>>,
+}
+
+impl Default for Scopes {
+    fn default() -> Self {
+        let mut funs = ScopeMap::new();
+        BUILTINS
+            .iter()
+            .for_each(|name| funs.define(name.to_string(), None));
+
+        Self {
+            vars: ScopeMap::new(),
+            funs,
+        }
+    }
+}
+
+pub fn convert(ast: &[AyNode<PStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+    convert_with(ast, &mut Warnings::default())
+}
+
+/// Binds a whole program, collecting its warnings.
+pub fn convert_with(
+    ast: &[AyNode<PStatement>],
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let file = SourceFile {
+        path: warnings.file.clone(),
+        ast: ast.to_vec(),
+    };
+
+    Ok(SourceFile::flatten(convert_files_with(&[file], warnings)?))
+}
+
+/// Binds a program file by file, so that names and warnings are traced back to their own file,
+/// unused names being only looked for in the last one.
+pub fn convert_files_with(
+    files: &[SourceFile<PStatement>],
+    warnings: &mut Warnings,
+) -> Result<Vec<SourceFile<Statement>>, Trace> {
+    match convert_files_partial(files, warnings) {
+        (bound, None) => Ok(bound),
+        (_, Some(trace)) => Err(trace),
+    }
+}
+
+/// Like [`convert_files_with`], but keeps the AST of a program that fails to bind, the names
+/// that could not be resolved being left as [`Expr::Error`] placeholders for typing to go on.
+pub fn convert_files_partial(
+    files: &[SourceFile<PStatement>],
+    warnings: &mut Warnings,
+) -> (Vec<SourceFile<Statement>>, Option<Trace>) {
+    let mut scopes = Scopes::default();
+    let mut refs = References::default();
+    let mut bound = vec![];
+    let mut errors = vec![];
+
+    for SourceFile { path, ast } in files {
+        refs.file = path.clone();
+        warnings.file = path.clone();
+        let (ast, trace) = resolve(ast, &mut scopes, &mut refs, warnings);
+        errors.extend(trace.map(|trace| match path {
+            Some(path) => trace.in_file(path),
+            None => trace,
+        }));
+        bound.push(SourceFile {
+            path: path.clone(),
+            ast,
+        });
+    }
+
+    let errors = Trace::collect(errors);
+    if errors.is_none() {
+        unused(&refs, warnings);
+    }
+
+    (bound, errors)
+}
+
+/// Binds `ast` on top of already existing scopes.
+pub fn convert_in(
+    ast: &[AyNode<PStatement>],
+    scopes: &mut Scopes,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    resolve_in(
+        ast,
+        scopes,
+        &mut References::default(),
+        &mut Warnings::default(),
+    )
+}
+
+/// Binds `ast` on top of already existing scopes, recording what every name resolves to.
+///
+/// Binding goes on after an error, so that every undefined name is reported at once.
+pub fn resolve_in(
+    ast: &[AyNode<PStatement>],
+    scopes: &mut Scopes,
+    refs: &mut References,
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    match resolve(ast, scopes, refs, warnings) {
+        (bound, None) => Ok(bound),
+        (_, Some(trace)) => Err(trace),
+    }
+}
+
+/// Binds `ast` along with the errors found on the way, if any.
+fn resolve(
+    ast: &[AyNode<PStatement>],
+    Scopes { vars, funs }: &mut Scopes,
+    refs: &mut References,
+    warnings: &mut Warnings,
+) -> (Vec<AyNode<Statement>>, Option<Trace>) {
+    let mut errors = vec![];
+    let bound = ast
+        .iter()
+        .map(|node| convert_statement(node, vars, funs, refs, warnings, &mut errors))
+        .collect::<Vec<AyNode<Statement>>>();
+
+    (bound, Trace::collect(errors))
+}
+
+/// Warns about the variables and parameters of the current file that are never read, once the
+/// whole program is bound.
+pub fn unused(refs: &References, warnings: &mut Warnings) {
+    for declaration in refs.list.iter().filter(|reference| {
+        reference.is_declaration()
+            && reference.location.file == refs.file
+            && reference.kind != SymbolKind::Function
+    }) {
+        let used = refs.list.iter().any(|reference| {
+            !reference.is_declaration()
+                && reference.declaration.as_ref() == Some(&declaration.location)
+        });
+        if used {
+            continue;
+        }
+
+        let name = &declaration.name;
+        let (message, label) = match declaration.kind {
+            SymbolKind::Parameter => (
+                message!("lint.unused_variable.parameter", name),
+                message!("lint.unused_variable.parameter_label"),
+            ),
+            _ => (
+                message!("lint.unused_variable", name),
+                message!("lint.unused_variable.label"),
+            ),
+        };
+        warnings.warn(
+            Stage::Binding,
+            Lint::UnusedVariable,
+            Error::from_span(declaration.location.span.clone(), &message).with_label(&label),
+        );
+    }
 }
-impl Node for Expr {}
 
-pub fn convert(mut ast: &Vec<AyNode<PStatement>>) -> Result<Vec<AyNode<Statement>>, Trace> {
-    let mut vars = ScopeMap::<String, ()>::new();
-    let mut funs = ScopeMap::<String, ()>::new();
+/// Warns about a declaration hiding `previous`, `None` standing for a builtin.
+fn shadowed(
+    warnings: &mut Warnings,
+    refs: &References,
+    location: &Location,
+    name: &str,
+    previous: Option<&Location>,
+) {
+    let mut err = Error::from_span(
+        location.span.clone(),
+        &match previous {
+            Some(_) => message!("lint.shadowed_name", name),
+            None => message!("lint.shadowed_name.builtin", name),
+        },
+    )
+    .with_label(&message!("lint.shadowed_name.label"));
+
+    if let Some(previous) = previous.filter(|previous| previous.file == refs.file) {
+        err = err.with_secondary_label(
+            previous.span.clone(),
+            &message!("lint.shadowed_name.previous"),
+        );
+    }
 
-    ast.iter()
-        .map(move |node| convert_statement(node, &mut vars, &mut funs))
-        .collect::<Result<Vec<AyNode<Statement>>, Trace>>()
+    warnings.warn(Stage::Binding, Lint::ShadowedName, err);
 }
 
 fn convert_statement(
     AyNode { span, inner }: &AyNode<PStatement>,
-    mut vars: &mut ScopeMap<String, ()>,
-    mut funs: &mut ScopeMap<String, ()>,
-) -> Result<AyNode<Statement>, Trace> {
+    vars: &mut ScopeMap<String, (SymbolKind, Location)>,
+    funs: &mut ScopeMap<String, Option<Location>>,
+    refs: &mut References,
+    warnings: &mut Warnings,
+    errors: &mut Vec<Trace>,
+) -> AyNode<Statement> {
     match inner {
         PStatement::VarDec { names, values } => {
-            names.iter().for_each(|name| vars.define(name.clone(), ()));
+            names.iter().for_each(|name| {
+                let location = refs.declare(SymbolKind::Variable, span, name);
+                if let Some((_, previous)) = vars.get(name) {
+                    shadowed(warnings, refs, &location, name, Some(previous));
+                }
+                vars.define(name.clone(), (SymbolKind::Variable, location));
+            });
 
-            Ok(AyNode {
+            AyNode {
                 span: span.clone(),
                 inner: Statement::VarDec(VarDec {
                     names: names.clone(),
-                    values: convert_iter!(expr values | vars funs)?,
+                    values: convert_iter!(expr values | vars funs refs errors),
                 }),
-            })
+            }
         }
         PStatement::FunDec { name, args, body } => {
-            funs.define(name.clone(), ());
-            Ok(AyNode {
+            let location = refs.declare(SymbolKind::Function, span, name);
+            if let Some(previous) = funs.get(name) {
+                shadowed(warnings, refs, &location, name, previous.as_ref());
+            }
+            funs.define(name.clone(), Some(location));
+
+            AyNode {
                 span: span.clone(),
                 inner: Statement::FunDec(FunDec {
                     name: name.clone(),
@@ -109,147 +563,265 @@ fn convert_statement(
                     body: wrap_scope!(
                         vars,
                         funs | {
-                            args.iter().for_each(|var| vars.define(var.clone(), ()));
-                            convert_iter!(statement body | vars funs)?
+                            args.iter().for_each(|var| {
+                                let location = refs.declare(SymbolKind::Parameter, span, var);
+                                if let Some((_, previous)) = vars.get(var) {
+                                    shadowed(warnings, refs, &location, var, Some(previous));
+                                }
+                                vars.define(var.clone(), (SymbolKind::Parameter, location));
+                            });
+                            convert_iter!(statement body | vars funs refs warnings errors)
                         }
                     ),
                 }),
-            })
+            }
         }
         PStatement::If {
             cond,
             then,
             otherwise,
-        } => Ok(AyNode {
+        } => AyNode {
             span: span.clone(),
             inner: Statement::If {
-                cond: convert_expr(cond, vars, funs)?,
-                then: wrap_scope!(vars, funs | { convert_iter!(statement then | vars funs)? }),
+                cond: convert_expr(cond, vars, funs, refs, errors),
+                then: wrap_scope!(
+                    vars,
+                    funs | { convert_iter!(statement then | vars funs refs warnings errors) }
+                ),
                 otherwise: wrap_scope!(
                     vars,
-                    funs | { convert_iter!(statement otherwise | vars funs)? }
+                    funs | { convert_iter!(statement otherwise | vars funs refs warnings errors) }
                 ),
             },
-        }),
-        PStatement::Loop { cond, body } => Ok(AyNode {
+        },
+        PStatement::Loop { cond, body } => AyNode {
             span: span.clone(),
             inner: Statement::Loop {
                 cond: cond
                     .clone()
-                    .map(|cond| convert_expr(&cond, vars, funs))
-                    .transpose()?,
-                body: wrap_scope!(vars, funs | { convert_iter!(statement body | vars funs)? }),
+                    .map(|cond| convert_expr(&cond, vars, funs, refs, errors)),
+                body: wrap_scope!(
+                    vars,
+                    funs | { convert_iter!(statement body | vars funs refs warnings errors) }
+                ),
+            },
+        },
+        PStatement::Test { name, body } => AyNode {
+            span: span.clone(),
+            inner: Statement::Test {
+                name: name.clone(),
+                body: wrap_scope!(
+                    vars,
+                    funs | { convert_iter!(statement body | vars funs refs warnings errors) }
+                ),
             },
-        }),
-        PStatement::Expr(expr) => Ok(AyNode {
+        },
+        PStatement::Expr(expr) => AyNode {
             span: span.clone(),
-            inner: Statement::Expr(convert_expr(expr, vars, funs)?),
-        }),
+            inner: Statement::Expr(convert_expr(expr, vars, funs, refs, errors)),
+        },
     }
 }
 
 fn convert_expr(
     AyNode { span, inner }: &AyNode<PExpr>,
-    mut vars: &mut ScopeMap<String, ()>,
-    mut funs: &mut ScopeMap<String, ()>,
-) -> Result<AyNode<Expr>, Trace> {
+    vars: &mut ScopeMap<String, (SymbolKind, Location)>,
+    funs: &mut ScopeMap<String, Option<Location>>,
+    refs: &mut References,
+    errors: &mut Vec<Trace>,
+) -> AyNode<Expr> {
     match inner {
         PExpr::Ident(name) => {
-            if let Some(rc) = vars.get(name) {
-                Ok(AyNode {
+            if let Some((kind, declaration)) = vars.get(name) {
+                refs.list.push(Reference {
+                    kind: *kind,
+                    name: name.clone(),
+                    location: refs.location(span, name),
+                    declaration: Some(declaration.clone()),
+                    tense: None,
+                });
+
+                AyNode {
                     span: span.clone(),
                     inner: Expr::Var(name.clone()),
-                })
+                }
             } else {
-                Err(Trace::new(
-                    Stage::Binding,
-                    Error::from_span(
-                        span.clone(),
-                        format!("Undefined variable: '{name}'{}", closest(vars, name)).as_ref(),
-                    ),
-                ))
+                errors.push(undefined(
+                    vars,
+                    |(_, location)| Some(location),
+                    refs,
+                    span,
+                    name,
+                    |name, candidates| BindingError::UndefinedVariable { name, candidates },
+                ));
+
+                AyNode {
+                    span: span.clone(),
+                    inner: Expr::Error,
+                }
             }
         }
         PExpr::FunCall { name, args } => match match_function(name, funs) {
-            Some(tense) => Ok(AyNode {
-                span: span.clone(),
-                inner: Expr::FunCall {
-                    tense,
-                    name: name.clone(),
-                    args: convert_iter!(expr args | vars funs)?,
-                },
-            }),
-            None => Err(Trace::new(
-                Stage::Binding,
-                Error::from_span(
-                    span.clone(),
-                    format!("Undefined function: '{name}'{}", closest(funs, name)).as_ref(),
-                ),
-            )),
+            Some((key, tense)) => {
+                refs.list.push(Reference {
+                    kind: SymbolKind::Function,
+                    name: key.clone(),
+                    location: refs.location(span, name),
+                    declaration: funs[key].clone(),
+                    tense: Some(tense.clone()),
+                });
+
+                AyNode {
+                    span: span.clone(),
+                    inner: Expr::FunCall {
+                        tense,
+                        name: name.clone(),
+                        args: convert_iter!(expr args | vars funs refs errors),
+                    },
+                }
+            }
+            None => {
+                errors.push(undefined(
+                    funs,
+                    Option::as_ref,
+                    refs,
+                    span,
+                    name,
+                    |name, candidates| BindingError::UndefinedFunction { name, candidates },
+                ));
+
+                // Arguments may hold errors of their own
+                args.iter().for_each(|arg| {
+                    convert_expr(arg, vars, funs, refs, errors);
+                });
+
+                AyNode {
+                    span: span.clone(),
+                    inner: Expr::Error,
+                }
+            }
         },
-        PExpr::Number(num) => Ok(AyNode {
+        PExpr::Number(num) => AyNode {
             span: span.clone(),
             inner: Expr::Number(*num),
-        }),
-        PExpr::String(string) => Ok(AyNode {
+        },
+        PExpr::String(string) => AyNode {
             span: span.clone(),
             inner: Expr::String(string.clone()),
-        }),
-        PExpr::Negated(expr) => Ok(AyNode {
+        },
+        PExpr::Negated(expr) => AyNode {
             span: span.clone(),
-            inner: Expr::Negated(Box::new(convert_expr(expr, vars, funs)?)),
-        }),
+            inner: Expr::Negated(Box::new(convert_expr(expr, vars, funs, refs, errors))),
+        },
         PExpr::Comparison {
             left,
             right,
             operator,
-        } => Ok(AyNode {
+        } => AyNode {
             span: span.clone(),
             inner: Expr::Comparison {
-                left: Box::new(convert_expr(left, vars, funs)?),
-                right: Box::new(convert_expr(right, vars, funs)?),
+                left: Box::new(convert_expr(left, vars, funs, refs, errors)),
+                right: Box::new(convert_expr(right, vars, funs, refs, errors)),
                 operator: operator.clone(),
             },
-        }),
-        PExpr::Array { items } => Ok(AyNode {
+        },
+        PExpr::Array { items } => AyNode {
             span: span.clone(),
             inner: Expr::Array {
-                items: convert_iter!(expr items | vars funs)?,
+                items: convert_iter!(expr items | vars funs refs errors),
             },
-        }),
+        },
     }
 }
 
-fn closest<T>(scope_map: &ScopeMap<String, T>, name: &str) -> String {
-    scope_map
-        .keys()
-        .map(|key| (key, distance::levenshtein(name, key)))
-        .min_by(|(_, d1), (_, d2)| usize::cmp(d1, d2))
-        .filter(|(key, dist)| *dist * 2 < key.len())
-        .map(|(key, _)| format!(". Maybe you meant: '{}'?", key.replace('.', "")))
-        .unwrap_or_else(|| "".to_owned())
+/// Names in scope close to `name` along with their value, in the form they would be called
+/// with, closest first.
+fn candidates<'a, T>(scope_map: &'a ScopeMap<String, T>, name: &str) -> Vec<(String, &'a T)> {
+    let mut res = scope_map
+        .iter()
+        .flat_map(|(key, value)| {
+            conjugations(key)
+                .into_iter()
+                .map(move |(form, _)| (form, value))
+        })
+        .map(|(form, value)| (distance::levenshtein(name, &form), form, value))
+        .filter(|(dist, form, _)| *dist * 2 < form.len())
+        .collect::<Vec<_>>();
+    res.sort_by_key(|(dist, _, _)| *dist);
+
+    let mut seen = vec![];
+    res.into_iter()
+        .filter(|(_, form, _)| {
+            let new = !seen.contains(form);
+            seen.push(form.clone());
+            new
+        })
+        .map(|(_, form, value)| (form, value))
+        .collect()
 }
 
-fn match_function(name: &str, funs: &ScopeMap<String, ()>) -> Option<Tense> {
-    funs.iter().find_map(|(key, fun)| {
-        if key.contains('.') {
-            let (left, right) = key.split_once('.').unwrap();
+/// Error for an unknown name, suggesting the closest one if any and pointing at its
+/// declaration when it is in the same file.
+fn undefined<T>(
+    scope_map: &ScopeMap<String, T>,
+    declaration: impl Fn(&T) -> Option<&Location>,
+    refs: &References,
+    span: &Span,
+    name: &str,
+    error: impl FnOnce(String, Vec<String>) -> BindingError,
+) -> Trace {
+    let name_span = span.find_word(name).unwrap_or_else(|| span.clone());
+    let candidates = candidates(scope_map, name);
 
-            if format!("{left}{right}") == name {
-                Some(Tense::Present)
-            } else if format!("{left}ìy{right}") == name {
-                Some(Tense::Imminent)
-            } else if format!("{left}ay{right}") == name {
-                Some(Tense::Future)
-            } else {
-                None
-            }
-        } else {
-            (key == name).then(|| Tense::Present)
+    let kind = error(
+        name.to_owned(),
+        candidates.iter().map(|(form, _)| form.clone()).collect(),
+    );
+    let code = ErrorKind::from(kind.clone()).code();
+
+    let mut err =
+        Error::new(name_span.clone(), kind).with_label(&message!(&format!("{code}.label"), name));
+
+    if let Some((form, value)) = candidates.into_iter().next() {
+        if let Some(location) = declaration(value).filter(|location| location.file == refs.file) {
+            err = err
+                .with_secondary_label(location.span.clone(), &message!(&format!("{code}.similar")));
         }
+
+        err = err.with_suggestion(Suggestion {
+            span: name_span,
+            replacement: form,
+        });
+    }
+
+    Trace::new(Stage::Binding, err)
+}
+
+/// Finds the declaration a possibly conjugated call refers to, along with the call's tense.
+pub fn match_function<'a, T>(
+    name: &str,
+    funs: &'a ScopeMap<String, T>,
+) -> Option<(&'a String, Tense)> {
+    funs.iter().find_map(|(key, _)| {
+        conjugations(key)
+            .into_iter()
+            .find(|(form, _)| form == name)
+            .map(|(_, tense)| (key, tense))
     })
 }
 
+/// Every form a function can be called with, e.g. `taron`, `tìyaron` and `tayaron` for `t.aron`.
+pub fn conjugations(name: &str) -> Vec<(String, Tense)> {
+    match name.split_once('.') {
+        Some((left, right)) => vec![
+            (format!("{left}{right}"), Tense::Present),
+            (format!("{left}ìy{right}"), Tense::Imminent),
+            (format!("{left}ay{right}"), Tense::Future),
+        ],
+        None => vec![(name.to_owned(), Tense::Present)],
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -276,7 +848,7 @@ mod test {
                     funs | {
                         funs.define("t.aron".to_owned(), ());
 
-                        vec!["taron", "tìyaron", "tayaron"]
+                        ["taron", "tìyaron", "tayaron"]
                             .iter()
                             .map(|name| (name, match_function(name, &funs)))
                             .for_each(|(name, res)| {
@@ -289,4 +861,29 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn test_every_error() {
+        let source = "ngop 'u a alu 1.\n\
+                      wìntxu fa b.\n\
+                      wìntxu fa c sì d.\n\
+                      tarom fa a.\n\
+                      ngop 'u e alu ke f.\n";
+        let ast = crate::ast::parsing::parse_content(None, source).unwrap();
+        let trace = convert(&ast).unwrap_err();
+
+        assert_eq!(
+            trace
+                .traces()
+                .map(|trace| trace.kind().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            [
+                "Undefined variable: 'b'",
+                "Undefined variable: 'c'",
+                "Undefined variable: 'd'",
+                "Undefined function: 'tarom'",
+                "Undefined variable: 'f'",
+            ]
+        );
+    }
 }
@@ -0,0 +1,370 @@
+use crate::{
+    ast::parsing::{AyParser, Rule},
+    error::{
+        kind::ParsingError,
+        span::Span,
+        trace::{Stage, Trace},
+        trace_error::{Error, Suggestion},
+    },
+    highlight::editor::terminals,
+};
+
+use std::fmt::Display;
+
+use {
+    pest::{
+        error::InputLocation,
+        iterators::{Pair, Pairs},
+        Parser,
+    },
+    serde::{ser::SerializeStruct, Serialize, Serializer},
+};
+
+/// Lossless concrete syntax tree: every byte of the source belongs to exactly one token.
+///
+/// Rule nodes come straight from pest, the text pest skips or matches with unnamed literals
+/// (whitespace, comments, keywords and punctuation) is kept as tokens between them.
+///
+/// Each node wraps the pest pair it was made from rather than copying it: the pair is the
+/// trivia-free view of the same parse, which [`build_ast`](crate::ast::parsing::build_ast) walks so
+/// that the AST and the tokens come from a single run of the parser and always agree.
+#[derive(Debug, Clone)]
+pub struct Cst<'i> {
+    children: Vec<CstElement<'i>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CstNode<'i> {
+    pair: Pair<'i, Rule>,
+    children: Vec<CstElement<'i>>,
+}
+
+#[derive(Debug, Clone)]
+pub enum CstElement<'i> {
+    Node(CstNode<'i>),
+    Token(Token<'i>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TokenKind {
+    Whitespace,
+    Comment,
+    Keyword,
+    Punctuation,
+    /// Whole text of a rule without children, e.g. an identifier or a number
+    Literal,
+}
+
+#[derive(Debug, Clone)]
+pub struct Token<'i> {
+    pub kind: TokenKind,
+    pub span: pest::Span<'i>,
+}
+
+pub fn parse(source: &str) -> Result<Cst<'_>, Trace> {
+    let pairs = AyParser::parse(Rule::program, source).map_err(|err| {
+        let typo = match err.location {
+            InputLocation::Pos(offset) | InputLocation::Span((offset, _)) => {
+                keyword_typo(source, offset)
+            }
+        };
+
+        let mut err = Error::from(err);
+        // The suggestion is printed as a help line of its own
+        if let Some((span, keyword)) = typo {
+            err = err.with_suggestion(Suggestion {
+                span,
+                replacement: keyword.to_owned(),
+            });
+        }
+
+        Trace::new(Stage::Parsing, err)
+    })?;
+
+    if let Some(position) = too_deep(&pairs) {
+        return Err(Trace::new(
+            Stage::Parsing,
+            Error::new(
+                position.span(&position).into(),
+                ParsingError::TooDeep { limit: MAX_NESTING },
+            ),
+        ));
+    }
+
+    Ok(Cst {
+        children: elements(source, pairs, 0, source.len()),
+    })
+}
+
+/// Nesting of rules past which programs are rejected, every later stage walking the tree
+/// recursively
+pub const MAX_NESTING: usize = 128;
+
+/// Start of the first rule nested deeper than [`MAX_NESTING`], if any.
+fn too_deep<'i>(pairs: &Pairs<'i, Rule>) -> Option<pest::Position<'i>> {
+    let mut depth = 0;
+
+    for token in pairs.clone().tokens() {
+        match token {
+            pest::Token::Start { pos, .. } if depth == MAX_NESTING => return Some(pos),
+            pest::Token::Start { .. } => depth += 1,
+            pest::Token::End { .. } => depth -= 1,
+        }
+    }
+
+    None
+}
+
+/// Word at `offset` along with the keyword it is closest to, if it looks like a misspelling.
+fn keyword_typo(source: &str, offset: usize) -> Option<(Span, &'static str)> {
+    let is_letter = |c: char| c.is_alphabetic() || c == '\'';
+
+    let start = source[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_letter(*c))
+        .last()
+        .map_or(offset, |(index, _)| index);
+    let end = source[offset..]
+        .find(|c: char| !is_letter(c))
+        .map_or(source.len(), |len| offset + len);
+    let word = &source[start..end];
+
+    let keywords = terminals();
+    if word.is_empty() || keywords.contains(&word) {
+        return None;
+    }
+
+    let (_, keyword) = keywords
+        .into_iter()
+        .map(|keyword| (distance::levenshtein(word, keyword), keyword))
+        .filter(|(dist, keyword)| *dist * 2 < keyword.chars().count())
+        .min_by_key(|(dist, _)| *dist)?;
+
+    Some((pest::Span::new(source, start, end)?.into(), keyword))
+}
+
+impl<'i> Cst<'i> {
+    /// Top-level rule nodes, without trivia
+    pub fn nodes(&self) -> impl Iterator<Item = &CstNode<'i>> {
+        nodes(&self.children)
+    }
+
+    /// Every token in source order
+    pub fn tokens(&self) -> impl Iterator<Item = &Token<'i>> {
+        let mut res = vec![];
+        tokens(&self.children, &mut res);
+        res.into_iter()
+    }
+}
+
+impl<'i> CstNode<'i> {
+    pub fn pair(&self) -> &Pair<'i, Rule> {
+        &self.pair
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.pair.as_rule()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &CstNode<'i>> {
+        nodes(&self.children)
+    }
+}
+
+fn nodes<'a, 'i>(children: &'a [CstElement<'i>]) -> impl Iterator<Item = &'a CstNode<'i>> {
+    children.iter().filter_map(|child| match child {
+        CstElement::Node(node) => Some(node),
+        CstElement::Token(_) => None,
+    })
+}
+
+fn tokens<'a, 'i>(children: &'a [CstElement<'i>], res: &mut Vec<&'a Token<'i>>) {
+    children.iter().for_each(|child| match child {
+        CstElement::Node(node) => tokens(&node.children, res),
+        CstElement::Token(token) => res.push(token),
+    })
+}
+
+/// Rule nodes from `pairs` interleaved with the tokens found between them.
+fn elements<'i>(
+    source: &'i str,
+    pairs: Pairs<'i, Rule>,
+    start: usize,
+    end: usize,
+) -> Vec<CstElement<'i>> {
+    let mut res = vec![];
+    let mut offset = start;
+
+    for pair in pairs {
+        let span = pair.as_span();
+
+        res.extend(gap(source, offset, span.start()));
+        offset = span.end();
+
+        let children = if span.start() == span.end() {
+            vec![]
+        } else if pair.clone().into_inner().peek().is_none() {
+            vec![CstElement::Token(Token {
+                kind: TokenKind::Literal,
+                span,
+            })]
+        } else {
+            elements(source, pair.clone().into_inner(), span.start(), span.end())
+        };
+
+        res.push(CstElement::Node(CstNode { pair, children }));
+    }
+
+    res.extend(gap(source, offset, end));
+    res
+}
+
+/// Splits text that does not belong to any rule into tokens.
+fn gap(source: &str, start: usize, end: usize) -> Vec<CstElement<'_>> {
+    let mut res = vec![];
+    let mut offset = start;
+
+    while offset < end {
+        let rest = &source[offset..end];
+        let first = rest.chars().next().unwrap();
+
+        let (kind, len) = if let Some(content) = rest.strip_prefix("''") {
+            // Comments end on a newline or on a second pair of quotes
+            let newline = content.find(['\n', '\r']).unwrap_or(content.len());
+            let len = match content.find("''") {
+                Some(quotes) if quotes < newline => quotes + 2,
+                _ => newline,
+            };
+            (TokenKind::Comment, len + 2)
+        } else if first.is_whitespace() {
+            (
+                TokenKind::Whitespace,
+                rest.find(|c: char| !c.is_whitespace())
+                    .unwrap_or(rest.len()),
+            )
+        } else if [',', '.'].contains(&first) {
+            (TokenKind::Punctuation, first.len_utf8())
+        } else {
+            (
+                TokenKind::Keyword,
+                rest.char_indices()
+                    .find(|(index, c)| {
+                        c.is_whitespace()
+                            || [',', '.'].contains(c)
+                            || rest[*index..].starts_with("''")
+                    })
+                    .map_or(rest.len(), |(index, _)| index),
+            )
+        };
+
+        res.push(CstElement::Token(Token {
+            kind,
+            span: pest::Span::new(source, offset, offset + len).unwrap(),
+        }));
+        offset += len;
+    }
+
+    res
+}
+
+impl Display for Cst<'_> {
+    /// Writes the exact source the tree was parsed from.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.tokens()
+            .try_for_each(|token| write!(f, "{}", token.span.as_str()))
+    }
+}
+
+impl Serialize for Cst<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.children.serialize(serializer)
+    }
+}
+
+impl Serialize for CstElement<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            CstElement::Node(node) => node.serialize(serializer),
+            CstElement::Token(token) => token.serialize(serializer),
+        }
+    }
+}
+
+impl Serialize for CstNode<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CstNode", 3)?;
+        state.serialize_field("rule", &format!("{:?}", self.rule()))?;
+        state.serialize_field("span", &Span::from(self.pair.as_span()))?;
+        state.serialize_field("children", &self.children)?;
+        state.end()
+    }
+}
+
+impl Serialize for Token<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Token", 2)?;
+        state.serialize_field("kind", &self.kind)?;
+        state.serialize_field("span", &Span::from(self.span))?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lossless() {
+        let source = "''header''\nngop 'u a alu 1 melo. ''trailing\n\
+                      txo a, tsakrr\n  wìntxu fa san it's ''kept'' sìk\ntxew.\n";
+        let cst = parse(source).unwrap();
+
+        assert_eq!(cst.to_string(), source);
+        assert_eq!(
+            cst.tokens()
+                .filter(|token| token.kind == TokenKind::Comment)
+                .map(|token| token.span.as_str())
+                .collect::<Vec<&str>>(),
+            ["''header''", "''trailing"]
+        );
+    }
+
+    #[test]
+    fn test_examples_lossless() {
+        let mut dirs = vec![std::path::PathBuf::from("./examples")];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().is_none_or(|ext| ext != "ay") {
+                    continue;
+                }
+
+                let source = std::fs::read_to_string(&path).unwrap();
+                // Examples of syntax errors have no tree
+                if let Ok(cst) = parse(&source) {
+                    assert_eq!(cst.to_string(), source, "{}", path.display());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_keyword_typo() {
+        let trace = parse("ngop lì'uking f alu wìntxu fa 1 txew.\n").unwrap_err();
+        let (_, err) = trace.errors().next().unwrap();
+
+        assert_eq!(
+            err.message(),
+            "expected a function declaration (`lì'ukìng`) or a variable declaration"
+        );
+        assert!(err.help().is_empty());
+        assert_eq!(err.suggestions()[0].span.as_str(), "lì'uking");
+        assert_eq!(err.suggestions()[0].replacement, "lì'ukìng");
+    }
+}
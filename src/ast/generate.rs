@@ -0,0 +1,327 @@
+use crate::{
+    ast::{binding, lib::SourceCode, parsing, typing},
+    error::trace::Trace,
+    highlight::Renderer,
+};
+
+/// Names that are not keywords, shared by variables and functions so that they clash sometimes
+const NAMES: [&str; 8] = [
+    "tìkan",
+    "nari",
+    "oeng",
+    "pamrel",
+    "rey",
+    "tsmuk",
+    "ikran",
+    "palulukan",
+];
+const BUILTINS: [(&str, usize); 6] = [
+    ("wìntxu", 1),
+    ("sung", 2),
+    ("luke", 2),
+    ("'ì'awnyu", 2),
+    ("teng", 2),
+    ("nìngay", 1),
+];
+const VAR_DECLARATORS: [&str; 4] = ["'u", "meu", "pxeu", "ayu"];
+/// Words pasted at random by [`Generator::mutate`], keywords of every rule and stray punctuation
+const WORDS: [&str; 18] = [
+    "ngop",
+    "lì'ukìng",
+    "fmetok",
+    "alu",
+    "txew",
+    "txo",
+    "tsakrr",
+    "leyn",
+    "kem si",
+    "ftang",
+    "fa",
+    "sì",
+    "ulte",
+    "san",
+    "sìk",
+    "sar",
+    ".",
+    ",",
+];
+/// Nesting past which only leaves are generated
+const MAX_DEPTH: usize = 4;
+
+/// Random programs following the grammar, mostly using the names in scope so that they get past
+/// binding and typing
+///
+/// The same seed always gives the same programs, so that a failure can be replayed.
+pub struct Generator {
+    state: u64,
+    depth: usize,
+    /// Functions declared so far with their number of arguments
+    functions: Vec<(String, usize)>,
+}
+
+impl Generator {
+    pub fn new(seed: u64) -> Self {
+        Generator {
+            // Xorshift never leaves zero
+            state: seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1,
+            depth: 0,
+            functions: vec![],
+        }
+    }
+
+    /// Xorshift64*
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+
+    /// Whole program of a few top-level statements and tests.
+    pub fn program(&mut self) -> String {
+        self.functions.clear();
+        (0..1 + self.below(6))
+            .map(|_| match self.chance(10) {
+                true => format!("ngop fmetok {} alu\n{}\ntxew.\n", self.name(), self.block()),
+                false => format!("{}.\n", self.statement()),
+            })
+            .collect()
+    }
+
+    /// Copy of `source` with a few characters removed, duplicated or replaced by keywords.
+    pub fn mutate(&mut self, source: &str) -> String {
+        let mut res = source.to_owned();
+
+        for _ in 0..1 + self.below(3) {
+            let boundaries = res
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain([res.len()])
+                .collect::<Vec<_>>();
+            let start = boundaries[self.below(boundaries.len())];
+            let end = boundaries[self.below(boundaries.len())].max(start);
+
+            match self.below(3) {
+                0 => res.replace_range(start..end, ""),
+                1 => {
+                    let copy = res[start..end].to_owned();
+                    res.insert_str(start, &copy);
+                }
+                _ => {
+                    let word = format!(" {} ", self.pick(&WORDS));
+                    res.insert_str(start, &word);
+                }
+            }
+        }
+
+        res
+    }
+
+    fn nested<T>(&mut self, generate: impl FnOnce(&mut Self) -> T) -> T {
+        self.depth += 1;
+        let res = generate(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn statement(&mut self) -> String {
+        let leaf = self.depth >= MAX_DEPTH;
+
+        match self.below(if leaf { 2 } else { 5 }) {
+            0 => self.var_dec(),
+            1 => self.expr(),
+            2 => self.fun_dec(),
+            3 => self.if_block(),
+            _ => self.loop_block(),
+        }
+    }
+
+    /// Statements separated by `ulte`.
+    fn block(&mut self) -> String {
+        self.nested(|generator| {
+            (0..1 + generator.below(3))
+                .map(|_| generator.statement())
+                .collect::<Vec<_>>()
+                .join(" ulte\n")
+        })
+    }
+
+    fn var_dec(&mut self) -> String {
+        let count = 1 + self.below(2);
+        // Sometimes more names than values
+        let values = if self.chance(10) { count + 1 } else { count };
+
+        format!(
+            "ngop {} {} alu {}",
+            self.pick(&VAR_DECLARATORS),
+            (0..count)
+                .map(|_| self.name())
+                .collect::<Vec<_>>()
+                .join(" sì "),
+            self.items(values),
+        )
+    }
+
+    fn fun_dec(&mut self) -> String {
+        let name = self.name();
+        let args = (0..self.below(3)).map(|_| self.name()).collect::<Vec<_>>();
+        self.functions.push((name.clone(), args.len()));
+
+        let mut res = format!("ngop lì'ukìng {name}");
+        if !args.is_empty() {
+            res += &format!(" fa {}", args.join(" sì "));
+        }
+        if self.chance(90) {
+            res += &format!(" alu\n{}\ntxew", self.block());
+        }
+        res
+    }
+
+    fn if_block(&mut self) -> String {
+        let cond = self.nested(Self::expr);
+        let then = self.block();
+
+        if self.chance(50) {
+            format!("txo {cond}, tsakrr\n{then},\ntxokefyaw\n{}", self.block())
+        } else {
+            format!("txo {cond}, tsakrr\n{then}\ntxew")
+        }
+    }
+
+    fn loop_block(&mut self) -> String {
+        let cond = if self.chance(80) {
+            format!(" vaykrr {}", self.nested(Self::expr))
+        } else {
+            String::new()
+        };
+        format!("leyn{cond} kem si\n{}\nftang", self.block())
+    }
+
+    fn expr(&mut self) -> String {
+        let leaf = self.depth >= MAX_DEPTH;
+
+        match self.below(if leaf { 3 } else { 7 }) {
+            0 => self.number(),
+            1 => self.name(),
+            2 => format!("san {} sìk", self.pick(&NAMES)),
+            3 => format!("ke {}", self.nested(Self::expr)),
+            4 => format!(
+                "may' {} sì {} livu teng",
+                self.atom(),
+                self.nested(Self::expr)
+            ),
+            // Empty arrays swallow the words after them, so they rarely parse
+            5 => match self.chance(5) {
+                true => "vezeykoyu".to_owned(),
+                false => {
+                    let count = 1 + self.below(3);
+                    format!("vezeykoyu {}", self.items(count))
+                }
+            },
+            _ => self.fun_call(),
+        }
+    }
+
+    /// Expression that cannot take the `sì` after it, as it has no list of its own.
+    fn atom(&mut self) -> String {
+        match self.below(4) {
+            0 => self.number(),
+            1 => self.name(),
+            2 => format!("san {} sìk", self.pick(&NAMES)),
+            _ => format!("ke {}", self.atom()),
+        }
+    }
+
+    /// Expressions separated by `sì`, only the last one being free to have items of its own.
+    fn items(&mut self, count: usize) -> String {
+        (0..count)
+            .map(|index| match index + 1 == count {
+                true => self.nested(Self::expr),
+                false => self.atom(),
+            })
+            .collect::<Vec<_>>()
+            .join(" sì ")
+    }
+
+    fn fun_call(&mut self) -> String {
+        let (name, arity) = match self.below(self.functions.len() + BUILTINS.len()) {
+            index if index < self.functions.len() => self.functions[index].clone(),
+            index => {
+                let (name, arity) = BUILTINS[index - self.functions.len()];
+                (name.to_owned(), arity)
+            }
+        };
+        // Sometimes the wrong number of arguments
+        let arity = if self.chance(10) {
+            self.below(3)
+        } else {
+            arity
+        };
+
+        match arity {
+            0 => format!("{name} si"),
+            _ => format!("{name} fa {}", self.items(arity)),
+        }
+    }
+
+    fn number(&mut self) -> String {
+        // Now and then too large to be a number
+        let len = if self.chance(1) { 30 } else { 4 };
+        let digits = (0..1 + self.below(len))
+            .map(|_| char::from(b'0' + self.below(8) as u8))
+            .collect::<String>();
+
+        match self.below(4) {
+            0 => format!("{digits} melo"),
+            1 => format!("{digits} pxelo"),
+            _ => digits,
+        }
+    }
+
+    fn name(&mut self) -> String {
+        self.pick(&NAMES).to_owned()
+    }
+}
+
+/// Parses, binds and types `source`, rendering its errors, for fuzzers to catch panics along the
+/// way.
+pub fn front_end(source: &str) -> Result<(), String> {
+    let check = || -> Result<(), Trace> {
+        let ast = parsing::parse(SourceCode::Content(source.to_owned()))?;
+        typing::convert(&binding::convert(&ast)?)?;
+        Ok(())
+    };
+
+    check().map_err(|trace| trace.render(&Renderer::Plain))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_front_end_never_panics() {
+        let mut generator = Generator::new(0);
+
+        for _ in 0..300 {
+            let program = generator.program();
+            let mutated = generator.mutate(&program);
+
+            // Errors are expected, panics are not
+            let _ = front_end(&program);
+            let _ = front_end(&mutated);
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use crate::error::span::Span;
+use crate::{ast::pattern::Term, error::span::Span};
 
-use std::str::FromStr;
-
-use {paste::paste, strum_macros::EnumString};
+use {
+    serde::Serialize,
+    strum_macros::{AsRefStr, EnumString},
+};
 
 #[derive(Debug)]
 pub enum SourceCode {
@@ -10,29 +11,89 @@ pub enum SourceCode {
     Content(String),
 }
 
+/// Statements of a single file of a program, in any of its ASTs
+#[derive(Clone, Debug)]
+pub struct SourceFile<S: Node> {
+    /// `None` for code not read from a file
+    pub path: Option<String>,
+    pub ast: Vec<AyNode<S>>,
+}
+
+impl<S: Node> SourceFile<S> {
+    /// Statements of every file, in the order they are run
+    pub fn flatten(files: Vec<Self>) -> Vec<AyNode<S>> {
+        files.into_iter().flat_map(|file| file.ast).collect()
+    }
+}
+
 /// Node containing a `Span` of code and the corresponding AST
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
 pub struct AyNode<Inner: Node> {
     pub span: Span,
     pub inner: Inner,
 }
 
-pub trait Node {}
+/// Node of an AST, opting in to [patterns](super::pattern) by describing itself as a term
+pub trait Node: Sized {
+    /// Tag and fields of the node, opaque to patterns by default
+    fn term(&self) -> Term {
+        Term::Opaque
+    }
+
+    /// Node described by `term`, `span` standing for the nodes built by a template, or `None` if
+    /// it cannot be rewritten
+    fn from_term(_term: &Term, _span: &Span) -> Option<Self> {
+        None
+    }
+
+    /// Blocks of nodes of the same kind right inside this one, searched by patterns
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        vec![]
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        vec![]
+    }
+}
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
 pub enum AyType {
     #[default]
     Bool,
     Number,
     String,
+    Unit,
     Array(Box<AyType>),
     Function {
         args: Vec<AyType>,
         result: Box<AyType>,
     },
+    /// Type variable, yet to be inferred
+    Var(usize),
 }
 
-#[derive(Debug, EnumString)]
+impl std::fmt::Display for AyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AyType::Bool => write!(f, "Bool"),
+            AyType::Number => write!(f, "Number"),
+            AyType::String => write!(f, "String"),
+            AyType::Unit => write!(f, "Unit"),
+            AyType::Array(item) => write!(f, "Array<{item}>"),
+            AyType::Function { args, result } => write!(
+                f,
+                "({}) -> {result}",
+                args.iter()
+                    .map(|arg| arg.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            AyType::Var(id) => write!(f, "?{id}"),
+        }
+    }
+}
+
+#[derive(Debug, EnumString, Serialize)]
 #[repr(i64)]
 pub enum Multiplier {
     #[strum(serialize = "melo")]
@@ -41,37 +102,21 @@ pub enum Multiplier {
     Triple = 3,
 }
 
-#[derive(Debug, EnumString, PartialEq, Eq, Clone)]
+#[derive(Debug, EnumString, AsRefStr, PartialEq, Eq, Clone, Serialize)]
 pub enum ComparisonOperator {
     #[strum(serialize = "teng")]
     Equals,
 }
 
+/// Converts every node of `$field` with `convert_$stex`, which reports its errors to a sink
+/// passed along `$iter` rather than failing.
 macro_rules! convert_iter {
     ($stex:ident $field:ident | $($iter:ident)+) => {
         paste::paste! {
             $field
                 .iter()
                 .map(|node| [<convert_ $stex>](node, $($iter),+))
-                .collect::<Result<Vec<_>, Trace>>()
-        }
-    };
-
-    ($stex:ident $field:ident | &mut $($iter:ident)+) => {
-        paste::paste! {
-            $field
-                .iter()
-                .map(|node| [<convert_ $stex>](node, &mut $($iter),+))
-                .collect::<Result<Vec<_>, Trace>>()
-        }
-    };
-
-    ($stex:ident $field:ident) => {
-        paste::paste! {
-            $field
-                .iter()
-                .map([<convert_ $stex>])
-                .collect::<Result<Vec<_>, Trace>>()
+                .collect::<Vec<_>>()
         }
     };
 }
@@ -1,4 +1,7 @@
 pub mod binding;
+pub mod cst;
+pub mod generate;
 pub mod lib;
 pub mod parsing;
+pub mod pattern;
 pub mod typing;
@@ -1,26 +1,32 @@
 use crate::{
-    ast::lib::*,
+    ast::{
+        cst::{self, Cst},
+        lib::*,
+        pattern::Term,
+    },
     error::{
+        kind::{describe, ParsingError},
+        lint::{Lint, Warnings},
+        locale::{self, message},
         span::Span,
-        trace::{Stage, Trace},
+        trace::{Stage, Trace, TraceError},
         trace_error::Error,
     },
+    highlight::render::{self, Role},
 };
 
 use std::{path::Path, str::FromStr};
 
-use pest::{
-    error::{Error as PestError, ErrorVariant},
-    iterators::{Pair, Pairs},
-    Parser,
-};
+use pest::iterators::{Pair, Pairs};
+
+use serde::Serialize;
 
 #[derive(Parser)]
 #[grammar = "../pest/grammar.pest"]
 pub struct AyParser;
 
 /// A statement is anything that cannot be expected to return a value.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum Statement {
     FunDec {
         name: String,
@@ -41,11 +47,109 @@ pub enum Statement {
         cond: Option<AyNode<Expr>>,
         body: Vec<AyNode<Statement>>,
     },
+    /// Top-level `ngop fmetok`, only run by `aysinvi test`
+    Test {
+        name: String,
+        body: Vec<AyNode<Statement>>,
+    },
+}
+
+/// Tags: `Fd(name, args, body)`, `Vd(names, values)`, `If(cond, then, otherwise)`,
+/// `Lp([cond], body)` and `Ts(name, body)`, expression statements being their expression
+impl Node for Statement {
+    fn term(&self) -> Term {
+        match self {
+            Statement::FunDec { name, args, body } => Term::node(
+                "Fd",
+                vec![Term::leaf(name), Term::leaves(args), Term::list(body)],
+            ),
+            Statement::VarDec { names, values } => {
+                Term::node("Vd", vec![Term::leaves(names), Term::list(values)])
+            }
+            Statement::Expr(expr) => expr.term(),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => Term::node(
+                "If",
+                vec![cond.term(), Term::list(then), Term::list(otherwise)],
+            ),
+            Statement::Loop { cond, body } => {
+                Term::node("Lp", vec![Term::list(cond), Term::list(body)])
+            }
+            Statement::Test { name, body } => {
+                Term::node("Ts", vec![Term::leaf(name), Term::list(body)])
+            }
+        }
+    }
+
+    fn from_term(term: &Term, span: &Span) -> Option<Self> {
+        let Term::Node { tag, fields, .. } = term else {
+            return None;
+        };
+
+        Some(match (tag.as_str(), &fields[..]) {
+            ("Fd", [name, args, body]) => Statement::FunDec {
+                name: name.as_leaf()?.to_owned(),
+                args: args.as_leaves()?,
+                body: body.as_nodes(span)?,
+            },
+            ("Vd", [names, values]) => {
+                let (names, values) = (names.as_leaves()?, values.as_nodes(span)?);
+                if names.len() != values.len() {
+                    return None;
+                }
+                Statement::VarDec { names, values }
+            }
+            ("If", [cond, then, otherwise]) => Statement::If {
+                cond: AyNode::from_term(cond, span)?,
+                then: then.as_nodes(span)?,
+                otherwise: otherwise.as_nodes(span)?,
+            },
+            ("Lp", [cond, body]) => Statement::Loop {
+                cond: match &cond.as_nodes(span)?[..] {
+                    [] => None,
+                    [cond] => Some(cond.clone()),
+                    _ => return None,
+                },
+                body: body.as_nodes(span)?,
+            },
+            ("Ts", [name, body]) => Statement::Test {
+                name: name.as_leaf()?.to_owned(),
+                body: body.as_nodes(span)?,
+            },
+            _ => Statement::Expr(AyNode::from_term(term, span)?),
+        })
+    }
+
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        match self {
+            Statement::FunDec { body, .. }
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec { .. } | Statement::Expr(_) => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        match self {
+            Statement::FunDec { body, .. }
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec { .. } | Statement::Expr(_) => vec![],
+        }
+    }
 }
-impl Node for Statement {}
 
 /// An expression is anything that is or returns a value.
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize)]
 pub enum Expr {
     FunCall {
         name: String,
@@ -64,26 +168,103 @@ pub enum Expr {
     Ident(String),
     Negated(Box<AyNode<Expr>>),
 }
-impl Node for Expr {}
+
+/// Tags: `Fc(name, args)`, `Ar(items)`, `Cp(left, right, operator)`, `Nb(number)`,
+/// `St(string)`, `Id(name)` and `Ng(expr)`
+impl Node for Expr {
+    fn term(&self) -> Term {
+        match self {
+            Expr::FunCall { name, args } => {
+                Term::node("Fc", vec![Term::leaf(name), Term::list(args)])
+            }
+            Expr::Array { items } => Term::node("Ar", vec![Term::list(items)]),
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => Term::node(
+                "Cp",
+                vec![left.term(), right.term(), Term::leaf(operator.as_ref())],
+            ),
+            Expr::Number(number) => Term::node("Nb", vec![Term::leaf(number)]),
+            Expr::String(string) => Term::node("St", vec![Term::leaf(string)]),
+            Expr::Ident(name) => Term::node("Id", vec![Term::leaf(name)]),
+            Expr::Negated(expr) => Term::node("Ng", vec![expr.term()]),
+        }
+    }
+
+    fn from_term(term: &Term, span: &Span) -> Option<Self> {
+        let Term::Node { tag, fields, .. } = term else {
+            return None;
+        };
+
+        Some(match (tag.as_str(), &fields[..]) {
+            ("Fc", [name, args]) => Expr::FunCall {
+                name: name.as_leaf()?.to_owned(),
+                args: args.as_nodes(span)?,
+            },
+            ("Ar", [items]) => Expr::Array {
+                items: items.as_nodes(span)?,
+            },
+            ("Cp", [left, right, operator]) => Expr::Comparison {
+                left: Box::new(AyNode::from_term(left, span)?),
+                right: Box::new(AyNode::from_term(right, span)?),
+                operator: operator.as_leaf()?.parse().ok()?,
+            },
+            ("Nb", [number]) => Expr::Number(number.as_leaf()?.parse().ok()?),
+            ("St", [string]) => Expr::String(string.as_leaf()?.to_owned()),
+            ("Id", [name]) => Expr::Ident(name.as_leaf()?.to_owned()),
+            ("Ng", [expr]) => Expr::Negated(Box::new(AyNode::from_term(expr, span)?)),
+            _ => return None,
+        })
+    }
+
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        match self {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => vec![items],
+            _ => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        match self {
+            Expr::FunCall { args: items, .. } | Expr::Array { items } => vec![items],
+            _ => vec![],
+        }
+    }
+}
+
+/// Inclusion of another file, e.g. `sar ayfayluyä negatedredund`.
+#[derive(PartialEq, Debug, Clone, Serialize)]
+pub struct ModUse {
+    /// Possessive directory names, endings included
+    pub path: Vec<String>,
+    pub name: String,
+}
+impl Node for ModUse {}
+
+/// A single file: its imports followed by its statements.
+pub type Module = (Vec<AyNode<ModUse>>, Vec<AyNode<Statement>>);
 
 /// Pushes new error onto stacktrace or returns pred(pair).
+///
+/// The frame is left out when it repeats the previous one, as nested rules of the same kind or
+/// rules spanning the same code tell nothing more.
 fn handle<F, T: Node>(parent: &Pair<Rule>, pair: Pair<Rule>, pred: &F) -> Result<AyNode<T>, Trace>
 where
     F: Fn(Pair<Rule>) -> Result<AyNode<T>, Trace>,
 {
     let (span, rule) = (parent.as_span(), parent.as_rule());
     pred(pair).map_err(|mut trace| {
-        trace.push::<Error>(
-            Stage::Parsing,
-            PestError::new_from_span(
-                ErrorVariant::ParsingError {
-                    positives: vec![rule],
-                    negatives: vec![],
-                },
-                span,
-            )
-            .into(),
-        );
+        let rule = describe(rule, locale::current());
+        let frame = Error::from_span(span.into(), &message!("trace.within", rule));
+
+        let repeated = trace.errors().last().is_some_and(|(_, last)| {
+            last.message() == frame.message() || last.line_col() == frame.line_col()
+        });
+        if !repeated {
+            trace.push(Stage::Parsing, frame);
+        }
         trace
     })
 }
@@ -108,32 +289,38 @@ macro_rules! fields {
             $(
                 let $field = $children
                     .next()
-                    .ok_or_else(|| Trace::new::<Error>(
-                        Stage::Parsing,
-                        PestError::new_from_span(
-                            ErrorVariant::ParsingError {
-                                positives: vec![$pair.as_rule()],
-                                negatives: vec![]
-                            },
-                            $pair.as_span()
-                        ).into()
-                    ))?;
+                    .ok_or_else(|| missing(&$pair, stringify!($field)))?;
             )*
         )?
     };
 }
 
+/// Error for a rule the grammar should never produce without its `field`.
+fn missing(pair: &Pair<Rule>, field: &str) -> Trace {
+    Trace::new(
+        Stage::Parsing,
+        Error::new(
+            pair.as_span().into(),
+            ParsingError::Unsupported {
+                construct: format!("`{:?}` without its `{field}`", pair.as_rule()),
+            },
+        ),
+    )
+}
+
 fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
     match pair.as_rule() {
-        Rule::expr => build_ast_from_expr(pair.into_inner().next().unwrap()),
-        Rule::negation => Ok(AyNode {
-            span: pair.as_span().into(),
-            inner: Expr::Negated(Box::new(handle(
-                &pair.clone(),
-                pair.into_inner().next().unwrap(),
-                &build_ast_from_expr,
-            )?)),
-        }),
+        Rule::expr => {
+            fields!(pair |> children: inner);
+            build_ast_from_expr(inner)
+        }
+        Rule::negation => {
+            fields!(pair |> children: operand);
+            Ok(AyNode {
+                span: pair.as_span().into(),
+                inner: Expr::Negated(Box::new(handle(&pair, operand, &build_ast_from_expr)?)),
+            })
+        }
         Rule::fun_call => {
             let span = pair.as_span();
             fields!(pair |> children: name);
@@ -164,9 +351,14 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
             let left = handle(&pair, left, &build_ast_from_expr)?;
             let right = handle(&pair, right, &build_ast_from_expr)?;
             let operator = ComparisonOperator::from_str(comparison.as_str()).map_err(|_| {
-                Trace::new_from_pair(
-                    &pair,
-                    format!("Unimplemented comparison operator: `{comparison}`"),
+                Trace::new(
+                    Stage::Parsing,
+                    Error::new(
+                        pair.as_span().into(),
+                        ParsingError::Unsupported {
+                            construct: format!("comparison operator `{comparison}`"),
+                        },
+                    ),
                 )
             })?;
 
@@ -182,39 +374,40 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
         Rule::number => {
             let span = pair.as_span();
             let mut elems = span.as_str().split_whitespace();
-            let number = elems.next().unwrap();
+            let number = elems.next().ok_or_else(|| missing(&pair, "digits"))?;
 
             // Bit unnecessary but better be safe than sorry
             let mult = if let Some(mult) = elems.next() {
                 Multiplier::from_str(mult).map_err(|_| {
-                    Trace::new::<Error>(
+                    Trace::new(
                         Stage::Parsing,
-                        PestError::new_from_span(
-                            ErrorVariant::CustomError {
-                                message: format!("Unimplemented multiplier: `{mult}`"),
+                        Error::new(
+                            span.into(),
+                            ParsingError::Unsupported {
+                                construct: format!("multiplier `{mult}`"),
                             },
-                            span,
-                        )
-                        .into(),
+                        ),
                     )
                 })? as i64
             } else {
                 1
             };
 
-            let result = i64::from_str_radix(number, 8).map_err(|_| {
-                Trace::new::<Error>(
-                    Stage::Parsing,
-                    PestError::new_from_span(
-                        ErrorVariant::ParsingError {
-                            positives: vec![Rule::number],
-                            negatives: vec![],
-                        },
-                        span,
+            // Too large either as written or once multiplied
+            let result = i64::from_str_radix(number, 8)
+                .ok()
+                .and_then(|number| number.checked_mul(mult))
+                .ok_or_else(|| {
+                    Trace::new(
+                        Stage::Parsing,
+                        Error::new(
+                            span.into(),
+                            ParsingError::InvalidNumber {
+                                literal: span.as_str().to_owned(),
+                            },
+                        ),
                     )
-                    .into(),
-                )
-            })? * mult;
+                })?;
 
             Ok(AyNode {
                 span: span.into(),
@@ -229,15 +422,14 @@ fn build_ast_from_expr(pair: Pair<Rule>) -> Result<AyNode<Expr>, Trace> {
             span: pair.as_span().into(),
             inner: Expr::Ident(pair.as_span().as_str().to_owned()),
         }),
-        rule => Err(Trace::new::<Error>(
+        rule => Err(Trace::new(
             Stage::AstBuilding,
-            PestError::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Missing expression-generating rule `{:?}` handling", rule),
+            Error::new(
+                pair.as_span().into(),
+                ParsingError::Unsupported {
+                    construct: format!("expression-generating rule `{rule:?}`"),
                 },
-                pair.as_span(),
-            )
-            .into(),
+            ),
         )),
     }
 }
@@ -292,16 +484,15 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
             });
 
             if idents.len() != values.len() {
-                return Err(Trace::new::<Error>(
+                return Err(Trace::new(
                     Stage::Parsing,
-                    PestError::new_from_span(
-                        ErrorVariant::ParsingError {
-                            positives: vec![Rule::var_dec],
-                            negatives: vec![],
+                    Error::new(
+                        span.into(),
+                        ParsingError::DeclarationMismatch {
+                            names: idents.len(),
+                            values: values.len(),
                         },
-                        span,
-                    )
-                    .into(),
+                    ),
                 ));
             }
 
@@ -357,126 +548,326 @@ fn build_ast_from_statement(pair: Pair<Rule>) -> Result<AyNode<Statement>, Trace
         }
         Rule::loop_block => {
             let span = pair.as_span();
-            fields!(pair |> children);
-
-            let mut child = children.next().unwrap();
+            fields!(pair |> children: first);
 
-            let (cond, body) = if children.peek().is_none() {
-                (
-                    None,
-                    handle_iter(&pair, &mut child.into_inner(), &build_ast_from_statement)?,
-                )
-            } else {
-                (
-                    Some(handle(&pair, child, &build_ast_from_expr)?),
-                    handle_iter(
-                        &pair,
-                        &mut children.next().unwrap().into_inner(),
-                        &build_ast_from_statement,
-                    )?,
-                )
+            // The condition is optional, the body is always last
+            let (cond, body) = match children.next() {
+                None => (None, first),
+                Some(body) => (Some(handle(&pair, first, &build_ast_from_expr)?), body),
             };
+            let body = handle_iter(&pair, &mut body.into_inner(), &build_ast_from_statement)?;
 
             Ok(AyNode {
                 span: span.into(),
                 inner: Statement::Loop { cond, body },
             })
         }
-        Rule::statement => Ok(build_ast_from_statement(pair.into_inner().next().unwrap())?),
-        rule => Err(Trace::new::<Error>(
+        Rule::test_dec => {
+            let span = pair.as_span();
+            fields!(pair |> children: name, body);
+
+            Ok(AyNode {
+                span: span.into(),
+                inner: Statement::Test {
+                    name: name.as_str().to_owned(),
+                    body: handle_iter(&pair, &mut body.into_inner(), &build_ast_from_statement)?,
+                },
+            })
+        }
+        Rule::statement => {
+            fields!(pair |> children: inner);
+            build_ast_from_statement(inner)
+        }
+        rule => Err(Trace::new(
             Stage::AstBuilding,
-            PestError::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Missing statement-generating rule `{:?}` handling", rule),
+            Error::new(
+                pair.as_span().into(),
+                ParsingError::Unsupported {
+                    construct: format!("statement-generating rule `{rule:?}`"),
                 },
-                pair.as_span(),
-            )
-            .into(),
+            ),
         )),
     }
 }
 
+/// Reads the source code, returning the path it was read from if any.
+pub fn read_source(source: SourceCode) -> Result<(Option<String>, String), Trace> {
+    match source {
+        SourceCode::File(path) => {
+            let content =
+                read_module(&path, Span::file_start()).map_err(|trace| trace.in_file(&path))?;
+            Ok((Some(path), content))
+        }
+        SourceCode::Content(content) => Ok((None, content)),
+    }
+}
+
+/// Reads the file at `path`, blaming `span` if it cannot be.
+fn read_module(path: &str, span: Span) -> Result<String, Trace> {
+    std::fs::read_to_string(path).map_err(|err| {
+        Trace::new(
+            Stage::AstBuilding,
+            Error::new(
+                span,
+                ParsingError::UnreadableModule {
+                    path: path.to_owned(),
+                    reason: err.to_string(),
+                },
+            ),
+        )
+    })
+}
+
 pub fn parse(source: SourceCode) -> Result<Vec<AyNode<Statement>>, Trace> {
-    let mut ast: Vec<AyNode<Statement>> = vec![];
+    parse_with(source, &mut Warnings::default())
+}
 
-    let (mut path, content) = match source {
-        SourceCode::File(path) => {
-            let unparsed_file = std::fs::read_to_string(path.as_str())
-                .unwrap_or_else(|_| panic!("Cannot read file at `{path}`"));
-            (Some(path), unparsed_file)
+/// Parses `source`, collecting its warnings and those of the modules it imports.
+pub fn parse_with(
+    source: SourceCode,
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    Ok(SourceFile::flatten(parse_files_with(source, warnings)?))
+}
+
+/// Parses `source` and the modules it imports, file by file.
+pub fn parse_files_with(
+    source: SourceCode,
+    warnings: &mut Warnings,
+) -> Result<Vec<SourceFile<Statement>>, Trace> {
+    let (path, content) = read_source(source)?;
+
+    parse_content_files(path.as_deref(), content.as_ref(), warnings).map_err(|trace| match &path {
+        Some(path) => trace.in_file(path),
+        None => trace,
+    })
+}
+
+/// Parses `content` as the file at `path`, following its `sar` imports.
+pub fn parse_content(path: Option<&str>, content: &str) -> Result<Vec<AyNode<Statement>>, Trace> {
+    parse_content_with(path, content, &mut Warnings::default())
+}
+
+pub fn parse_content_with(
+    path: Option<&str>,
+    content: &str,
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    Ok(SourceFile::flatten(parse_content_files(
+        path, content, warnings,
+    )?))
+}
+
+/// Parses `content` as the file at `path` and the modules it imports, which come first.
+pub fn parse_content_files(
+    path: Option<&str>,
+    content: &str,
+    warnings: &mut Warnings,
+) -> Result<Vec<SourceFile<Statement>>, Trace> {
+    parse_imports(path, content, warnings, &mut vec![], &mut vec![])
+}
+
+/// Like [`parse_content_files`], `importers` being the files whose imports are being parsed, each
+/// one importing the next, and `visited` the files already parsed, which are only imported once.
+fn parse_imports(
+    path: Option<&str>,
+    content: &str,
+    warnings: &mut Warnings,
+    importers: &mut Vec<String>,
+    visited: &mut Vec<String>,
+) -> Result<Vec<SourceFile<Statement>>, Trace> {
+    let mut files = vec![];
+
+    warnings.file = path.map(str::to_owned);
+    let (uses, statements) = parse_module_with(content, warnings)?;
+    importers.extend(path.map(str::to_owned));
+
+    for module in uses {
+        if let Some(path) = path {
+            let path = module_path(path, &module)?;
+            if let Some(start) = importers.iter().position(|importer| *importer == path) {
+                return Err(Trace::new(
+                    Stage::AstBuilding,
+                    Error::new(
+                        module.span,
+                        ParsingError::CyclicImport {
+                            cycle: importers[start..].iter().chain([&path]).cloned().collect(),
+                            path,
+                        },
+                    ),
+                ));
+            }
+            if visited.contains(&path) {
+                continue;
+            }
+            let content = read_module(&path, module.span)?;
+
+            eprintln!("Using {path}");
+            let mut imported = parse_imports(Some(&path), &content, warnings, importers, visited)
+                .map_err(|trace| trace.in_file(&path))?;
+            // The tests of a module are only run from the module itself
+            imported.iter_mut().for_each(|file| {
+                file.ast
+                    .retain(|statement| !matches!(statement.inner, Statement::Test { .. }))
+            });
+            files.extend(imported);
+        } else {
+            let AyNode { span, inner } = module;
+            return Err(Trace::new(
+                Stage::AstBuilding,
+                Error::new(
+                    span,
+                    ParsingError::ImportWithoutPath {
+                        module: inner.name.clone(),
+                    },
+                ),
+            ));
         }
-        SourceCode::Content(content) => (None, content),
-    };
+    }
 
-    let pairs = AyParser::parse(Rule::program, content.as_ref()).map_err(Error::from)?;
+    visited.extend(importers.pop());
+    warnings.file = path.map(str::to_owned);
+    files.push(SourceFile {
+        path: path.map(str::to_owned),
+        ast: statements,
+    });
 
-    for pair in pairs.clone() {
-        recursive_print(Some(&pair), 0);
+    Ok(files)
+}
+
+/// Path of the file imported by `module` from the file at `path`.
+pub fn module_path(path: &str, module: &AyNode<ModUse>) -> Result<String, Trace> {
+    let ModUse { path: dirs, name } = &module.inner;
+    let mut res = Path::new(path).parent().unwrap_or(Path::new("")).to_owned();
+
+    for possessive in dirs {
+        let dir = possessive
+            .strip_suffix("yä")
+            .or_else(|| possessive.strip_suffix('ä'))
+            .ok_or_else(|| {
+                Trace::new(
+                    Stage::AstBuilding,
+                    Error::new(
+                        module.span.clone(),
+                        ParsingError::Unsupported {
+                            construct: format!("possessive `{possessive}` without its `ä` ending"),
+                        },
+                    ),
+                )
+            })?;
+        res.push(dir);
     }
+    res.push(format!("{name}.ay"));
 
-    for pair in pairs {
+    Ok(res.to_string_lossy().into_owned())
+}
+
+/// Builds the AST of a single file without following its `sar` imports.
+pub fn parse_module(content: &str) -> Result<Module, Trace> {
+    parse_module_with(content, &mut Warnings::default())
+}
+
+pub fn parse_module_with(content: &str, warnings: &mut Warnings) -> Result<Module, Trace> {
+    let cst = cst::parse(content)?;
+    let module = build_ast(&cst)?;
+
+    warnings.suppress_in(&cst);
+    unreachable(&module.1, warnings);
+
+    Ok(module)
+}
+
+/// Warns about the first statement following a loop without condition in each block.
+fn unreachable(block: &[AyNode<Statement>], warnings: &mut Warnings) {
+    let endless = block
+        .iter()
+        .position(|statement| matches!(statement.inner, Statement::Loop { cond: None, .. }));
+    if let Some((endless, next)) =
+        endless.and_then(|index| Some((&block[index], block.get(index + 1)?)))
+    {
+        let keyword = endless
+            .span
+            .find_word("leyn")
+            .unwrap_or(endless.span.clone());
+        warnings.warn(
+            Stage::Parsing,
+            Lint::UnreachableCode,
+            Error::from_span(next.span.clone(), &message!("lint.unreachable_code"))
+                .with_label(&message!("lint.unreachable_code.label"))
+                .with_secondary_label(keyword, &message!("lint.unreachable_code.loop")),
+        );
+    }
+
+    for statement in block {
+        match &statement.inner {
+            Statement::FunDec { body, .. }
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => unreachable(body, warnings),
+            Statement::If {
+                then, otherwise, ..
+            } => {
+                unreachable(then, warnings);
+                unreachable(otherwise, warnings);
+            }
+            Statement::VarDec { .. } | Statement::Expr(_) => {}
+        }
+    }
+}
+
+/// Derives the AST from the trivia-free part of a concrete syntax tree, i.e. the pest pairs its
+/// top-level nodes wrap.
+pub fn build_ast(cst: &Cst) -> Result<Module, Trace> {
+    let mut uses = vec![];
+    let mut ast = vec![];
+
+    for pair in cst.nodes().map(|node| node.pair().clone()) {
         match pair.as_rule() {
             Rule::mod_use => {
-                if let Some(ref mut path) = path {
-                    // Bit of a nightmare but it seems to work
-                    let parent = Path::new(path).parent().unwrap().to_str().unwrap();
-
-                    let path = format!(
-                        "{parent}/{}",
-                        pair.clone()
-                            .into_inner()
-                            .map(|child| match child.as_rule() {
-                                Rule::possessive => format!(
-                                    "{}/",
-                                    child.as_str().strip_suffix("yä").unwrap_or_else(|| child
-                                        .as_str()
-                                        .strip_suffix('ä')
-                                        .unwrap())
-                                ),
-                                Rule::ident => format!("{}.ay", child.as_str()),
-                                _ => unreachable!(),
-                            })
-                            .collect::<String>()
-                    );
-
-                    eprintln!("Using {path}");
-                    ast.extend(parse(SourceCode::File(path.clone()))?);
-                } else {
-                    return Err(Trace::new::<Error>(
-                        Stage::AstBuilding,
-                        PestError::new_from_span(
-                            ErrorVariant::CustomError {
-                                message: "Missing script directory information".to_owned(),
+                let span = pair.as_span();
+                let mut path = vec![];
+                let mut name = String::new();
+
+                for child in pair.into_inner() {
+                    match child.as_rule() {
+                        Rule::possessive => path.push(child.as_str().to_owned()),
+                        Rule::ident => name = child.as_str().to_owned(),
+                        rule => Err(Error::new(
+                            child.as_span().into(),
+                            ParsingError::Unsupported {
+                                construct: format!("rule `{rule:?}` in an import"),
                             },
-                            pair.as_span(),
-                        )
-                        .into(),
-                    ));
+                        ))?,
+                    }
                 }
+
+                uses.push(AyNode {
+                    span: span.into(),
+                    inner: ModUse { path, name },
+                });
             }
-            Rule::statement => ast.push(build_ast_from_statement(pair)?),
+            Rule::statement | Rule::test_dec => ast.push(build_ast_from_statement(pair)?),
             Rule::EOI => {}
-            unknown_rule => Err(PestError::new_from_span(
-                ErrorVariant::CustomError {
-                    message: format!("Unknown rule: {:?}", unknown_rule),
+            unknown_rule => Err(Error::new(
+                pair.as_span().into(),
+                ParsingError::Unsupported {
+                    construct: format!("rule `{unknown_rule:?}`"),
                 },
-                pair.as_span(),
-            ))
-            .map_err(Error::from)?,
+            ))?,
         }
     }
 
-    Ok(ast)
+    Ok((uses, ast))
 }
 
 pub fn recursive_print(cur: Option<&Pair<Rule>>, depth: usize) {
     if let Some(node) = cur {
         let rule = node.as_rule();
 
+        let renderer = render::current();
         println!(
-            "{}\x1b[1;33m{:?}\x1b[0m:'{}'",
-            format_args!("\x1b[31m{}\x1b[0m", "|   ".repeat(depth)),
-            rule,
+            "{}{}:'{}'",
+            renderer.paint(Role::Gutter, &"|   ".repeat(depth)),
+            renderer.paint(Role::Stage, &format!("{rule:?}")),
             node.as_span()
                 .as_str()
                 .lines()
@@ -489,3 +880,34 @@ pub fn recursive_print(cur: Option<&Pair<Rule>>, depth: usize) {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::kind::ErrorKind;
+
+    #[test]
+    fn test_cyclic_import() {
+        let trace = parse_files_with(
+            SourceCode::File("examples/features/imports/invalid/cycle/a.ay".into()),
+            &mut Warnings::default(),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            trace.kind(),
+            Some(&ErrorKind::from(ParsingError::CyclicImport {
+                path: "examples/features/imports/invalid/cycle/a.ay".to_owned(),
+                cycle: vec![
+                    "examples/features/imports/invalid/cycle/a.ay".to_owned(),
+                    "examples/features/imports/invalid/cycle/b.ay".to_owned(),
+                    "examples/features/imports/invalid/cycle/a.ay".to_owned(),
+                ],
+            }))
+        );
+        assert_eq!(
+            trace.file(),
+            Some("examples/features/imports/invalid/cycle/b.ay")
+        );
+    }
+}
@@ -0,0 +1,654 @@
+//! Generic AST pattern replace
+//!
+//! Rewrite rules are written as a pattern followed by an action, `/d` deleting what the pattern
+//! matches and `/s/` substituting it with a template:
+//!
+//! - `Fd(_, _, [Vd(_, _)])/d` deletes every function declaration whose body is a single variable
+//!   declaration;
+//! - `[Fd(name, argnames, body), Fc(name, args)]/s/[Vd(argnames, args), body]` replaces a
+//!   function declaration followed by a call to it with a declaration of its arguments and its
+//!   body.
+//!
+//! Patterns are made of nodes, `Tag(field, …)`, lists, `[item, …]`, the wildcard `_`, literals,
+//! `"text"` or `12`, and capture names. A name captured twice must match equal terms both times,
+//! and `..` or `..name` stands for any number of items of a list. A top-level list matches
+//! consecutive items of a list of nodes, e.g. the statements of a block. Nodes can leave out
+//! their trailing fields, so that the same rule applies to every layer: `Fc(name, args)` also
+//! matches the calls of the bound AST, which have their tense as a third field, and builds
+//! calls in the present tense.
+//!
+//! Node types opt in through [`Node`], describing themselves as a [`Term`] and, for those that
+//! can be rewritten, building themselves back from one. The tags each layer uses are listed on
+//! its `Node` implementations. The parsed and bound ASTs can be rewritten, the typed one only
+//! searched and deleted from, as the types of the nodes a template builds would be unknown.
+
+use crate::{
+    ast::lib::{AyNode, Node},
+    error::{
+        kind::RewritingError,
+        span::Span,
+        trace::{Stage, Trace},
+        trace_error::Error,
+    },
+};
+
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use pest::{iterators::Pair, Parser};
+
+#[derive(Parser)]
+#[grammar = "../pest/pattern.pest"]
+struct PatternParser;
+
+/// Generic view of a node, compared by patterns
+#[derive(Debug, Clone)]
+pub enum Term {
+    Node {
+        tag: String,
+        fields: Vec<Term>,
+        /// Where the node comes from, `None` for nodes built by a template
+        span: Option<Span>,
+    },
+    List(Vec<Term>),
+    /// Name, number or string
+    Leaf(String),
+    /// Node that does not take part in patterns, only matched by `_` and captures
+    Opaque,
+}
+
+impl PartialEq for Term {
+    /// Compares the shape of terms, wherever they come from.
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Term::Node { tag, fields, .. },
+                Term::Node {
+                    tag: other_tag,
+                    fields: other_fields,
+                    ..
+                },
+            ) => tag == other_tag && fields == other_fields,
+            (Term::List(items), Term::List(other_items)) => items == other_items,
+            (Term::Leaf(leaf), Term::Leaf(other_leaf)) => leaf == other_leaf,
+            _ => false,
+        }
+    }
+}
+
+impl Term {
+    pub fn node(tag: &str, fields: Vec<Term>) -> Self {
+        Term::Node {
+            tag: tag.to_owned(),
+            fields,
+            span: None,
+        }
+    }
+
+    pub fn leaf(leaf: impl ToString) -> Self {
+        Term::Leaf(leaf.to_string())
+    }
+
+    /// List of leaves, e.g. the names of a declaration.
+    pub fn leaves(leaves: &[String]) -> Self {
+        Term::List(leaves.iter().map(Term::leaf).collect())
+    }
+
+    /// List of the terms of `nodes`.
+    pub fn list<'a, N: Node + 'a>(nodes: impl IntoIterator<Item = &'a AyNode<N>>) -> Self {
+        Term::List(nodes.into_iter().map(AyNode::term).collect())
+    }
+
+    /// Fields of a node tagged `tag`.
+    pub fn fields(&self, tag: &str) -> Option<&[Term]> {
+        match self {
+            Term::Node {
+                tag: node_tag,
+                fields,
+                ..
+            } if node_tag == tag => Some(fields),
+            _ => None,
+        }
+    }
+
+    pub fn as_leaf(&self) -> Option<&str> {
+        match self {
+            Term::Leaf(leaf) => Some(leaf),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Term]> {
+        match self {
+            Term::List(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Texts of a list of leaves.
+    pub fn as_leaves(&self) -> Option<Vec<String>> {
+        self.as_list()?
+            .iter()
+            .map(|item| item.as_leaf().map(str::to_owned))
+            .collect()
+    }
+
+    /// Nodes of a list, `span` standing for the nodes built by a template.
+    pub fn as_nodes<N: Node>(&self, span: &Span) -> Option<Vec<AyNode<N>>> {
+        self.as_list()?
+            .iter()
+            .map(|item| AyNode::from_term(item, span))
+            .collect()
+    }
+
+    pub fn span(&self) -> Option<&Span> {
+        match self {
+            Term::Node { span, .. } => span.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl<N: Node> AyNode<N> {
+    /// Term of the inner node, along with where it comes from.
+    pub fn term(&self) -> Term {
+        match self.inner.term() {
+            Term::Node { tag, fields, .. } => Term::Node {
+                tag,
+                fields,
+                span: Some(self.span.clone()),
+            },
+            term => term,
+        }
+    }
+
+    /// Node described by `term`, at `span` unless the term knows where it comes from.
+    pub fn from_term(term: &Term, span: &Span) -> Option<Self> {
+        let span = term.span().unwrap_or(span);
+
+        Some(AyNode {
+            span: span.clone(),
+            inner: N::from_term(term, span)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pattern {
+    Node {
+        tag: String,
+        fields: Vec<Pattern>,
+    },
+    List(Vec<Pattern>),
+    Wildcard,
+    /// Any number of list items, captured under the name if any
+    Rest(Option<String>),
+    Literal(String),
+    Capture(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Delete,
+    /// Items replacing the match, captures holding lists being spliced into them
+    Substitute(Vec<Pattern>),
+}
+
+/// Rewrite rule, parsed from its textual form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rewrite {
+    /// Consecutive items matched
+    pub pattern: Vec<Pattern>,
+    pub action: Action,
+}
+
+/// Terms captured by name
+pub type Captures = BTreeMap<String, Term>;
+
+/// Consecutive nodes of a list matched by a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// Span of the first node matched
+    pub span: Span,
+    pub captures: Captures,
+}
+
+impl FromStr for Rewrite {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut pairs = PatternParser::parse(Rule::rewrite, source)
+            .map_err(|err| format!("Invalid rewrite rule:\n{err}"))?;
+        let (Some(pattern), Some(action)) = (pairs.next(), pairs.next()) else {
+            return Err("Invalid rewrite rule: missing its pattern or action".to_owned());
+        };
+
+        let pattern = items(pattern)?;
+        let action = match action.as_rule() {
+            Rule::substitute => {
+                let template = action
+                    .into_inner()
+                    .next()
+                    .ok_or("Invalid rewrite rule: `/s/` without a template")?;
+                Action::Substitute(items(template)?)
+            }
+            _ => Action::Delete,
+        };
+
+        let rewrite = Rewrite { pattern, action };
+        rewrite.check()?;
+        Ok(rewrite)
+    }
+}
+
+/// Items of a top-level pattern or template, a single one unless it is a list.
+fn items(pair: Pair<Rule>) -> Result<Vec<Pattern>, String> {
+    match pattern(pair)? {
+        Pattern::List(items) => Ok(items),
+        pattern => Ok(vec![pattern]),
+    }
+}
+
+fn pattern(pair: Pair<Rule>) -> Result<Pattern, String> {
+    Ok(match pair.as_rule() {
+        Rule::node => {
+            let mut children = pair.into_inner();
+            let tag = children.next().map_or("", |tag| tag.as_str()).to_owned();
+            Pattern::Node {
+                tag,
+                fields: children.map(pattern).collect::<Result<_, _>>()?,
+            }
+        }
+        Rule::list => Pattern::List(pair.into_inner().map(pattern).collect::<Result<_, _>>()?),
+        Rule::wildcard => Pattern::Wildcard,
+        Rule::rest => Pattern::Rest(
+            pair.into_inner()
+                .next()
+                .map(|name| name.as_str().to_owned()),
+        ),
+        Rule::literal => {
+            let inner = pair.into_inner().next();
+            Pattern::Literal(inner.map_or("", |inner| inner.as_str()).to_owned())
+        }
+        Rule::name => Pattern::Capture(pair.as_str().to_owned()),
+        other => return Err(format!("Invalid rewrite rule: unexpected `{other:?}`")),
+    })
+}
+
+impl Rewrite {
+    /// Rejects rests outside of lists, and templates using what the pattern does not capture.
+    fn check(&self) -> Result<(), String> {
+        let mut captured = vec![];
+        self.pattern
+            .iter()
+            .try_for_each(|item| check(item, true, &mut captured))?;
+
+        if let Action::Substitute(template) = &self.action {
+            let mut used = vec![];
+            template
+                .iter()
+                .try_for_each(|item| check(item, true, &mut used))?;
+
+            let unnamed = |item: &Pattern| matches!(item, Pattern::Wildcard | Pattern::Rest(None));
+            if template.iter().any(|item| contains(item, &unnamed)) {
+                return Err("Templates can only copy what is captured by name".to_owned());
+            }
+            if let Some(name) = used.iter().find(|name| !captured.contains(name)) {
+                return Err(format!(
+                    "`{name}` is used by the template but never captured"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Every match in `list` and the lists nested in its nodes, those in a match excluded.
+    pub fn find<N: Node>(&self, list: &[AyNode<N>]) -> Vec<Match> {
+        let terms = list.iter().map(AyNode::term).collect::<Vec<_>>();
+        let mut res = vec![];
+
+        let mut index = 0;
+        while index < list.len() {
+            match self.match_at(&terms, index) {
+                Some((end, captures)) => {
+                    res.push(Match {
+                        span: list[index].span.clone(),
+                        captures,
+                    });
+                    index = end;
+                }
+                None => {
+                    list[index]
+                        .inner
+                        .children()
+                        .into_iter()
+                        .for_each(|children| res.extend(self.find(children)));
+                    index += 1;
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Applies the action to every match of [`Rewrite::find`], returning how many there were.
+    ///
+    /// Nodes put in place of a match are not searched again, so that rules making what they
+    /// match always end. `list` is left untouched if a replacement cannot be built.
+    pub fn apply<N: Node + Clone>(&self, list: &mut Vec<AyNode<N>>) -> Result<usize, Trace> {
+        let mut rewritten = list.clone();
+        let count = self.rewrite(&mut rewritten)?;
+        *list = rewritten;
+
+        Ok(count)
+    }
+
+    fn rewrite<N: Node>(&self, list: &mut Vec<AyNode<N>>) -> Result<usize, Trace> {
+        let terms = list.iter().map(AyNode::term).collect::<Vec<_>>();
+        let mut nodes = std::mem::take(list).into_iter().enumerate();
+        let mut count = 0;
+
+        while let Some((index, mut node)) = nodes.next() {
+            let Some((end, captures)) = self.match_at(&terms, index) else {
+                for children in node.inner.children_mut() {
+                    count += self.rewrite(children)?;
+                }
+                list.push(node);
+                continue;
+            };
+
+            count += 1;
+            // The rest of the match goes along with its first node
+            nodes.by_ref().take(end - index - 1).for_each(drop);
+
+            if let Action::Substitute(template) = &self.action {
+                for term in instantiate_items(template, &captures) {
+                    let replacement = AyNode::from_term(&term, &node.span).ok_or_else(|| {
+                        Trace::new(
+                            Stage::Rewriting,
+                            Error::new(
+                                node.span.clone(),
+                                RewritingError::Unbuildable {
+                                    term: term.to_string(),
+                                },
+                            ),
+                        )
+                    })?;
+                    list.push(replacement);
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// End of the shortest match starting at `index` along with its captures.
+    fn match_at(&self, terms: &[Term], index: usize) -> Option<(usize, Captures)> {
+        (index + 1..=terms.len()).find_map(|end| {
+            let mut captures = Captures::new();
+            matches_list(&self.pattern, &terms[index..end], &mut captures)
+                .then_some((end, captures))
+        })
+    }
+}
+
+/// Validates `pattern`, collecting the names it captures.
+fn check(pattern: &Pattern, in_list: bool, names: &mut Vec<String>) -> Result<(), String> {
+    match pattern {
+        Pattern::Node { fields, .. } => fields
+            .iter()
+            .try_for_each(|field| check(field, false, names)),
+        Pattern::List(items) => items.iter().try_for_each(|item| check(item, true, names)),
+        Pattern::Rest(_) if !in_list => Err("`..` can only stand for items of a list".to_owned()),
+        Pattern::Rest(Some(name)) | Pattern::Capture(name) => {
+            names.push(name.clone());
+            Ok(())
+        }
+        Pattern::Rest(None) | Pattern::Wildcard | Pattern::Literal(_) => Ok(()),
+    }
+}
+
+fn contains(pattern: &Pattern, pred: &impl Fn(&Pattern) -> bool) -> bool {
+    pred(pattern)
+        || match pattern {
+            Pattern::Node { fields: items, .. } | Pattern::List(items) => {
+                items.iter().any(|item| contains(item, pred))
+            }
+            _ => false,
+        }
+}
+
+/// Binds `name` to `term`, unless it is already bound to a different one.
+fn capture(name: &str, term: Term, captures: &mut Captures) -> bool {
+    match captures.get(name) {
+        Some(bound) => *bound == term,
+        None => {
+            captures.insert(name.to_owned(), term);
+            true
+        }
+    }
+}
+
+fn matches(pattern: &Pattern, term: &Term, captures: &mut Captures) -> bool {
+    match (pattern, term) {
+        (Pattern::Wildcard, _) => true,
+        (Pattern::Capture(name), _) => capture(name, term.clone(), captures),
+        (Pattern::Literal(literal), Term::Leaf(leaf)) => literal == leaf,
+        (
+            Pattern::Node { tag, fields },
+            Term::Node {
+                tag: term_tag,
+                fields: term_fields,
+                ..
+            },
+        ) => {
+            // Trailing fields can be left out, e.g. the tense of calls past parsing
+            tag == term_tag
+                && fields.len() <= term_fields.len()
+                && fields
+                    .iter()
+                    .zip(term_fields)
+                    .all(|(field, term)| matches(field, term, captures))
+        }
+        (Pattern::List(items), Term::List(terms)) => matches_list(items, terms, captures),
+        _ => false,
+    }
+}
+
+/// Whether `patterns` match the whole of `terms`, rests taking as few items as they can.
+fn matches_list(patterns: &[Pattern], terms: &[Term], captures: &mut Captures) -> bool {
+    // Captures are only kept from the attempt that succeeds
+    let mut attempt = |len: usize, rest: &[Pattern], first: &dyn Fn(&mut Captures) -> bool| {
+        let mut attempt = captures.clone();
+        let res = first(&mut attempt) && matches_list(rest, &terms[len..], &mut attempt);
+        if res {
+            *captures = attempt;
+        }
+        res
+    };
+
+    match patterns.split_first() {
+        None => terms.is_empty(),
+        Some((Pattern::Rest(name), rest)) => (0..=terms.len()).any(|len| {
+            attempt(len, rest, &|captures| match name {
+                Some(name) => capture(name, Term::List(terms[..len].to_vec()), captures),
+                None => true,
+            })
+        }),
+        Some((first, rest)) => {
+            !terms.is_empty() && attempt(1, rest, &|captures| matches(first, &terms[0], captures))
+        }
+    }
+}
+
+fn instantiate(template: &Pattern, captures: &Captures) -> Term {
+    match template {
+        Pattern::Node { tag, fields } => Term::Node {
+            tag: tag.clone(),
+            fields: fields
+                .iter()
+                .map(|field| instantiate(field, captures))
+                .collect(),
+            span: None,
+        },
+        Pattern::List(items) => Term::List(instantiate_items(items, captures)),
+        Pattern::Literal(literal) => Term::Leaf(literal.clone()),
+        Pattern::Capture(name) | Pattern::Rest(Some(name)) => captures[name].clone(),
+        // Rejected when parsing templates
+        Pattern::Wildcard | Pattern::Rest(None) => Term::Opaque,
+    }
+}
+
+/// Terms of list items, captured lists being spliced.
+fn instantiate_items(items: &[Pattern], captures: &Captures) -> Vec<Term> {
+    items
+        .iter()
+        .flat_map(|item| match instantiate(item, captures) {
+            Term::List(items) if matches!(item, Pattern::Capture(_) | Pattern::Rest(_)) => items,
+            term => vec![term],
+        })
+        .collect()
+}
+
+impl Display for Term {
+    /// Writes the term the way patterns are.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |terms: &[Term]| {
+            terms
+                .iter()
+                .map(Term::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match self {
+            Term::Node { tag, fields, .. } => write!(f, "{tag}({})", join(fields)),
+            Term::List(items) => write!(f, "[{}]", join(items)),
+            Term::Leaf(leaf) => write!(f, "{leaf:?}"),
+            Term::Opaque => write!(f, "_"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{binding, parsing, typing},
+        error::{code::Code, kind::ErrorKind},
+    };
+
+    fn parse(source: &str) -> Vec<AyNode<parsing::Statement>> {
+        parsing::parse_content(None, source).unwrap()
+    }
+
+    const README_SOURCE: &str = "ngop lì'ukìng f alu ngop 'u a alu 1 txew.\n\
+                                 ngop lì'ukìng mesung fa a alu wìntxu fa a ulte sung fa a sì a txew.\n\
+                                 mesung fa 2.\n";
+    const README_RESULT: &str = "ngop 'u a alu 2.\nwìntxu fa a.\nsung fa a sì a.\n";
+
+    /// Applies both rules of the README, checking what they find on the way.
+    fn readme_rules<N: Node + Clone>(ast: &mut Vec<AyNode<N>>) {
+        let delete = "Fd(_,_,[Vd(_, _)])/d".parse::<Rewrite>().unwrap();
+        assert_eq!(delete.apply(ast).unwrap(), 1);
+
+        let inline = "[Fd(name,argnames,body),Fc(name,args)]/s/[Vd(argnames,args),body]"
+            .parse::<Rewrite>()
+            .unwrap();
+        assert_eq!(inline.find(ast)[0].captures["name"], Term::leaf("mesung"));
+        assert_eq!(inline.apply(ast).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_readme_rules() {
+        let mut ast = parse(README_SOURCE);
+        readme_rules(&mut ast);
+        assert_eq!(Term::list(&ast), Term::list(&parse(README_RESULT)));
+
+        // Calls of the bound AST have a tense the rules leave out
+        let bind = |source| binding::convert(&parse(source)).unwrap();
+        let mut bound = bind(README_SOURCE);
+        readme_rules(&mut bound);
+        assert_eq!(Term::list(&bound), Term::list(&bind(README_RESULT)));
+    }
+
+    #[test]
+    fn test_layers() {
+        let source = "ngop 'u a alu 1.\n\
+                      txo a, tsakrr wìntxu fa a ulte wìntxu fa 2 ulte a txew.\n";
+        let bound = binding::convert(&parse(source)).unwrap();
+        let typed = typing::convert(&bound).unwrap();
+
+        // Calls repeating the argument of an earlier one, in any block
+        let rewrite = "[Fc(\"wìntxu\", [x], _), .., x]/d"
+            .parse::<Rewrite>()
+            .unwrap();
+        let spans = |matches: Vec<Match>| {
+            matches
+                .iter()
+                .map(|found| {
+                    (
+                        found.span.as_str().to_owned(),
+                        found.captures["x"].to_string(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let expected = [("wìntxu fa a ".to_owned(), "Id(\"a\")".to_owned())];
+        assert_eq!(spans(rewrite.find(&bound)), expected);
+        assert_eq!(spans(rewrite.find(&typed)), expected);
+
+        let mut typed = typed;
+        assert_eq!(rewrite.apply(&mut typed).unwrap(), 1);
+        let typing::Statement::If { then, .. } = &typed[1].inner else {
+            panic!("{:?}", typed[1]);
+        };
+        assert!(then.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        for (rule, message) in [
+            ("Fd(..)/d", "`..` can only stand for items of a list"),
+            (
+                "Fc(name, _)/s/Fc(other, [])",
+                "`other` is used by the template but never captured",
+            ),
+            (
+                "Fc(_, args)/s/[_]",
+                "Templates can only copy what is captured by name",
+            ),
+        ] {
+            assert_eq!(rule.parse::<Rewrite>(), Err(message.to_owned()));
+        }
+        assert!("Fd(_)/x".parse::<Rewrite>().is_err());
+
+        // Templates must describe nodes of the layer rewritten
+        let mut ast = parse("wìntxu fa 1.\n");
+        let trace = "Fc(_, args)/s/Vd([], [Nb(1)])"
+            .parse::<Rewrite>()
+            .unwrap()
+            .apply(&mut ast)
+            .unwrap_err();
+        let (_, err) = trace.errors().next().unwrap();
+        assert_eq!(err.code(), Some(Code::RewriteFailure));
+        assert_eq!(
+            err.message(),
+            "Cannot build a node out of `Vd([], [Nb(\"1\")])`"
+        );
+
+        // Nor can typed nodes be built
+        let bound = binding::convert(&ast).unwrap();
+        let mut typed = typing::convert(&bound).unwrap();
+        let trace = "Fc(name, args)/s/Fc(name, args)"
+            .parse::<Rewrite>()
+            .unwrap()
+            .apply(&mut typed)
+            .unwrap_err();
+        assert_eq!(
+            trace.kind().map(ErrorKind::code),
+            Some(Code::RewriteFailure)
+        );
+    }
+}
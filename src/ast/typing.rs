@@ -1,51 +1,85 @@
 use crate::{
     ast::{
-        binding::{Expr as BExpr, Statement as BStatement},
-        lib::{convert_iter, AyNode, AyType, ComparisonOperator, Multiplier, Node},
+        binding::{self, match_function, Expr as BExpr, Statement as BStatement, Tense},
+        lib::{AyNode, AyType, ComparisonOperator, Node, SourceFile},
+        pattern::Term,
     },
     error::{
+        kind::{BindingError, ErrorKind, TypingError},
+        lint::{Lint, Warnings},
+        locale::message,
         span::Span,
         trace::{Stage, Trace, TraceError},
         trace_error::Error,
     },
 };
 
-use std::rc::Rc;
+use {quickscope::ScopeMap, serde::Serialize};
 
-use paste::paste;
-
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub enum Tense {
-    Present,
-    Imminent,
-    Future,
-}
-
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub struct FunDec {
-    name: String,
-    args: Vec<Rc<VarDec>>,
-    body: Vec<AyNode<Statement>>,
+    pub name: String,
+    pub args: Vec<String>,
+    pub fun_type: AyType,
+    pub body: Vec<AyNode<Statement>>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub struct VarDec {
-    names: Vec<String>,
-    values: Vec<TypedExpr>,
+    pub names: Vec<String>,
+    pub values: Vec<TypedExpr>,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub struct TypedExpr {
-    expr_type: AyType,
-    inner: Expr,
+    pub span: Span,
+    pub expr_type: AyType,
+    pub inner: Expr,
+}
+
+/// Tags: the bound AST's ones, types being left out
+///
+/// Typed nodes can be searched and deleted, but not built by templates as their types would be
+/// unknown.
+impl Node for TypedExpr {
+    fn term(&self) -> Term {
+        let list = |exprs: &[TypedExpr]| Term::List(exprs.iter().map(TypedExpr::term).collect());
+        let (tag, fields) = match &self.inner {
+            Expr::FunCall {
+                tense, name, args, ..
+            } => (
+                "Fc",
+                vec![Term::leaf(name), list(args), Term::leaf(tense.as_ref())],
+            ),
+            Expr::Array { items } => ("Ar", vec![list(items)]),
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => (
+                "Cp",
+                vec![left.term(), right.term(), Term::leaf(operator.as_ref())],
+            ),
+            Expr::Number(number) => ("Nb", vec![Term::leaf(number)]),
+            Expr::String(string) => ("St", vec![Term::leaf(string)]),
+            Expr::Var(name) => ("Id", vec![Term::leaf(name)]),
+            Expr::Negated(expr) => ("Ng", vec![expr.term()]),
+            Expr::Error => ("Er", vec![]),
+        };
+
+        Term::Node {
+            tag: tag.to_owned(),
+            fields,
+            span: Some(self.span.clone()),
+        }
+    }
 }
-impl Node for TypedExpr {}
 
 /// A statement is anything that cannot be expected to return a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub enum Statement {
-    FunDec(Rc<FunDec>),
-    VarDec(Rc<VarDec>),
+    FunDec(FunDec),
+    VarDec(VarDec),
     Expr(TypedExpr),
     If {
         cond: TypedExpr,
@@ -56,16 +90,86 @@ pub enum Statement {
         cond: Option<TypedExpr>,
         body: Vec<AyNode<Statement>>,
     },
+    Test {
+        name: String,
+        body: Vec<AyNode<Statement>>,
+    },
+}
+
+/// Tags: the bound AST's ones
+///
+/// Like expressions, typed statements are never built by templates: rules can only delete them.
+impl Node for Statement {
+    fn term(&self) -> Term {
+        match self {
+            Statement::FunDec(FunDec {
+                name, args, body, ..
+            }) => Term::node(
+                "Fd",
+                vec![Term::leaf(name), Term::leaves(args), Term::list(body)],
+            ),
+            Statement::VarDec(VarDec { names, values }) => Term::node(
+                "Vd",
+                vec![
+                    Term::leaves(names),
+                    Term::List(values.iter().map(TypedExpr::term).collect()),
+                ],
+            ),
+            Statement::Expr(expr) => expr.term(),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => Term::node(
+                "If",
+                vec![cond.term(), Term::list(then), Term::list(otherwise)],
+            ),
+            Statement::Loop { cond, body } => Term::node(
+                "Lp",
+                vec![
+                    Term::List(cond.iter().map(TypedExpr::term).collect()),
+                    Term::list(body),
+                ],
+            ),
+            Statement::Test { name, body } => {
+                Term::node("Ts", vec![Term::leaf(name), Term::list(body)])
+            }
+        }
+    }
+
+    fn children(&self) -> Vec<&[AyNode<Self>]> {
+        match self {
+            Statement::FunDec(FunDec { body, .. })
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec(_) | Statement::Expr(_) => vec![],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Vec<AyNode<Self>>> {
+        match self {
+            Statement::FunDec(FunDec { body, .. })
+            | Statement::Loop { body, .. }
+            | Statement::Test { body, .. } => vec![body],
+            Statement::If {
+                then, otherwise, ..
+            } => vec![then, otherwise],
+            Statement::VarDec(_) | Statement::Expr(_) => vec![],
+        }
+    }
 }
-impl Node for Statement {}
 
 /// An expression is anything that is or returns a value.
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
 pub enum Expr {
     FunCall {
         tense: Tense,
-        dec: Rc<FunDec>,
         name: String,
+        /// Type of the function at this call site
+        fun_type: AyType,
         args: Vec<TypedExpr>,
     },
     Array {
@@ -78,110 +182,856 @@ pub enum Expr {
     },
     Number(i64),
     String(String),
-    Var(Rc<VarDec>),
+    Var(String),
     Negated(Box<TypedExpr>),
+    /// Placeholder for an expression that failed to bind or type, of a type unifying with any
+    Error,
 }
 impl Node for Expr {}
 
-pub fn convert(mut ast: &Vec<AyNode<BStatement>>) -> Result<Vec<AyNode<Statement>>, Trace> {
-    ast.iter()
-        .map(convert_statement)
-        .collect::<Result<Vec<AyNode<Statement>>, Trace>>()
+/// Function type whose `generics` variables are instantiated anew at each call
+#[derive(Clone, Debug)]
+struct Scheme {
+    generics: Vec<usize>,
+    body: AyType,
 }
 
-fn convert_statement(
-    AyNode { span, inner }: &AyNode<BStatement>,
-) -> Result<AyNode<Statement>, Trace> {
-    match inner {
-        BStatement::Expr(expr) => Ok(AyNode {
-            span: span.clone(),
-            inner: Statement::Expr(convert_expr(expr)?),
-        }),
-        _ => todo!(),
-    }
-}
-
-fn convert_expr(AyNode { span, inner }: &AyNode<BExpr>) -> Result<TypedExpr, Trace> {
-    match inner {
-        BExpr::Number(number) => Ok(TypedExpr {
-            expr_type: AyType::Number,
-            inner: Expr::Number(*number),
-        }),
-        BExpr::String(string) => Ok(TypedExpr {
-            expr_type: AyType::String,
-            inner: Expr::String(string.clone()),
-        }),
-        BExpr::Array { items } => {
-            let items = convert_iter!(expr items)?;
-
-            Ok(TypedExpr {
-                expr_type: AyType::Array(Box::new(
-                    items
-                        .get(0)
-                        .ok_or_else(|| {
-                            Trace::new(
-                                Stage::Typing,
-                                Error::from_span(span.clone(), "Cannot defined empty arrays"),
-                            )
-                        })?
-                        .expr_type
-                        .clone(),
-                )),
-                inner: Expr::Array {
-                    items: items.clone(),
+/// Types visible to the typer, kept alive between inputs by the REPL
+#[derive(Clone, Default)]
+pub struct Types {
+    vars: ScopeMap<String, AyType>,
+    funs: ScopeMap<String, Scheme>,
+    substitution: Vec<Option<AyType>>,
+}
+
+/// Hindley-Milner style inference: type variables are unified as constraints are met, and
+/// function types are generalized once their body has been typed.
+///
+/// Typing goes on after an error, the faulty expression getting a fresh type so that it does
+/// not cause errors of its own further on.
+#[derive(Default)]
+struct Typer {
+    vars: ScopeMap<String, AyType>,
+    funs: ScopeMap<String, Scheme>,
+    substitution: Vec<Option<AyType>>,
+    errors: Vec<Trace>,
+    /// Lints found in the current file, reported once it is typed
+    warnings: Vec<(Lint, Error)>,
+}
+
+pub fn convert(ast: &[AyNode<BStatement>]) -> Result<Vec<AyNode<Statement>>, Trace> {
+    convert_with(ast, &mut Warnings::default())
+}
+
+/// Types a whole program, collecting its warnings.
+pub fn convert_with(
+    ast: &[AyNode<BStatement>],
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let file = SourceFile {
+        path: warnings.file.clone(),
+        ast: ast.to_vec(),
+    };
+
+    Ok(SourceFile::flatten(convert_files_with(&[file], warnings)?))
+}
+
+/// Types a program file by file, so that errors and warnings are traced back to their own file.
+pub fn convert_files_with(
+    files: &[SourceFile<BStatement>],
+    warnings: &mut Warnings,
+) -> Result<Vec<SourceFile<Statement>>, Trace> {
+    let mut typer = Typer::default();
+    let mut typed = vec![];
+    let mut errors = vec![];
+
+    for SourceFile { path, ast } in files {
+        let ast = ast
+            .iter()
+            .map(|node| typer.statement(node))
+            .collect::<Vec<_>>();
+        typer.dropped_values(&ast);
+
+        warnings.file = path.clone();
+        std::mem::take(&mut typer.warnings)
+            .into_iter()
+            .for_each(|(lint, err)| warnings.warn(Stage::Typing, lint, err));
+        errors.extend(
+            std::mem::take(&mut typer.errors)
+                .into_iter()
+                .map(|trace| match path {
+                    Some(path) => trace.in_file(path),
+                    None => trace,
+                }),
+        );
+
+        typed.push(SourceFile {
+            path: path.clone(),
+            ast: ast.into_iter().map(|(node, _)| node).collect(),
+        });
+    }
+
+    if let Some(trace) = Trace::collect(errors) {
+        return Err(trace);
+    }
+
+    typed
+        .iter_mut()
+        .flat_map(|file| file.ast.iter_mut())
+        .for_each(|node| typer.resolve_statement(&mut node.inner));
+
+    Ok(typed)
+}
+
+/// Types `ast` on top of already known types.
+pub fn convert_in(
+    ast: &[AyNode<BStatement>],
+    types: &mut Types,
+) -> Result<Vec<AyNode<Statement>>, Trace> {
+    let Types {
+        vars,
+        funs,
+        substitution,
+    } = std::mem::take(types);
+    let mut typer = Typer {
+        vars,
+        funs,
+        substitution,
+        ..Default::default()
+    };
+
+    let mut typed = ast
+        .iter()
+        .map(|node| typer.statement(node).0)
+        .collect::<Vec<_>>();
+    typed
+        .iter_mut()
+        .for_each(|node| typer.resolve_statement(&mut node.inner));
+
+    let Typer {
+        vars,
+        funs,
+        substitution,
+        errors,
+        ..
+    } = typer;
+    *types = Types {
+        vars,
+        funs,
+        substitution,
+    };
+
+    match Trace::collect(errors) {
+        Some(trace) => Err(trace),
+        None => Ok(typed),
+    }
+}
+
+impl Typer {
+    fn fresh(&mut self) -> AyType {
+        self.substitution.push(None);
+        AyType::Var(self.substitution.len() - 1)
+    }
+
+    /// Applies every substitution known so far.
+    fn resolve(&self, ty: &AyType) -> AyType {
+        match ty {
+            AyType::Var(id) => self.substitution[*id]
+                .as_ref()
+                .map_or_else(|| ty.clone(), |ty| self.resolve(ty)),
+            AyType::Array(item) => AyType::Array(Box::new(self.resolve(item))),
+            AyType::Function { args, result } => AyType::Function {
+                args: args.iter().map(|arg| self.resolve(arg)).collect(),
+                result: Box::new(self.resolve(result)),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn occurs(&self, id: usize, ty: &AyType) -> bool {
+        match self.resolve(ty) {
+            AyType::Var(other) => other == id,
+            AyType::Array(item) => self.occurs(id, &item),
+            AyType::Function { args, result } => {
+                args.iter().any(|arg| self.occurs(id, arg)) || self.occurs(id, &result)
+            }
+            _ => false,
+        }
+    }
+
+    fn unify(&mut self, left: &AyType, right: &AyType) -> bool {
+        match (self.resolve(left), self.resolve(right)) {
+            (AyType::Var(left), AyType::Var(right)) if left == right => true,
+            (AyType::Var(id), other) | (other, AyType::Var(id)) => {
+                if self.occurs(id, &other) {
+                    return false;
+                }
+
+                self.substitution[id] = Some(other);
+                true
+            }
+            (AyType::Array(left), AyType::Array(right)) => self.unify(&left, &right),
+            (
+                AyType::Function {
+                    args: left_args,
+                    result: left_result,
                 },
-            })
+                AyType::Function {
+                    args: right_args,
+                    result: right_result,
+                },
+            ) => {
+                left_args.len() == right_args.len()
+                    && left_args
+                        .iter()
+                        .zip(right_args.iter())
+                        .all(|(left, right)| self.unify(left, right))
+                    && self.unify(&left_result, &right_result)
+            }
+            (left, right) => left == right,
+        }
+    }
+
+    /// Unifies the type found at `span` with the expected one, `explain` adding to the error
+    /// where the expectation comes from.
+    fn expect(
+        &mut self,
+        span: &Span,
+        expected: &AyType,
+        found: &AyType,
+        explain: impl FnOnce(Error) -> Error,
+    ) {
+        if !self.unify(expected, found) {
+            let (expected, found) = (self.resolve(expected), self.resolve(found));
+            self.errors.push(Trace::new(
+                Stage::Typing,
+                explain(
+                    Error::new(
+                        span.clone(),
+                        TypingError::Mismatch {
+                            expected,
+                            found: found.clone(),
+                        },
+                    )
+                    .with_label(&message!("AY0201.label", found)),
+                ),
+            ));
+        }
+    }
+
+    /// Records an error and returns the placeholder taking the place of the faulty expression.
+    fn placeholder(&mut self, trace: Trace) -> (AyType, Expr) {
+        self.errors.push(trace);
+        (self.fresh(), Expr::Error)
+    }
+
+    fn free_vars(&self, ty: &AyType, res: &mut Vec<usize>) {
+        match self.resolve(ty) {
+            AyType::Var(id) if !res.contains(&id) => res.push(id),
+            AyType::Array(item) => self.free_vars(&item, res),
+            AyType::Function { args, result } => {
+                args.iter().for_each(|arg| self.free_vars(arg, res));
+                self.free_vars(&result, res);
+            }
+            _ => {}
+        }
+    }
+
+    /// Quantifies over the variables that are not bound by an enclosing scope.
+    fn generalize(&self, ty: &AyType) -> Scheme {
+        let mut bound = vec![];
+        self.vars
+            .iter()
+            .for_each(|(_, ty)| self.free_vars(ty, &mut bound));
+
+        let mut generics = vec![];
+        self.free_vars(ty, &mut generics);
+        generics.retain(|id| !bound.contains(id));
+
+        Scheme {
+            generics,
+            body: self.resolve(ty),
+        }
+    }
+
+    fn instantiate(&mut self, Scheme { generics, body }: &Scheme) -> AyType {
+        fn replace(ty: &AyType, mapping: &[(usize, AyType)]) -> AyType {
+            match ty {
+                AyType::Var(id) => mapping
+                    .iter()
+                    .find(|(generic, _)| generic == id)
+                    .map_or_else(|| ty.clone(), |(_, fresh)| fresh.clone()),
+                AyType::Array(item) => AyType::Array(Box::new(replace(item, mapping))),
+                AyType::Function { args, result } => AyType::Function {
+                    args: args.iter().map(|arg| replace(arg, mapping)).collect(),
+                    result: Box::new(replace(result, mapping)),
+                },
+                other => other.clone(),
+            }
+        }
+
+        let mapping = generics
+            .iter()
+            .map(|id| (*id, self.fresh()))
+            .collect::<Vec<_>>();
+
+        replace(body, &mapping)
+    }
+
+    /// Types a block in its own scope, returning the type of its last statement.
+    fn block(&mut self, body: &[AyNode<BStatement>]) -> (Vec<AyNode<Statement>>, AyType) {
+        self.vars.push_layer();
+        self.funs.push_layer();
+
+        let statements = body
+            .iter()
+            .map(|node| self.statement(node))
+            .collect::<Vec<_>>();
+        let ty = statements.last().map_or(AyType::Unit, |(_, ty)| ty.clone());
+        self.dropped_values(&statements);
+
+        self.vars.pop_layer();
+        self.funs.pop_layer();
+
+        (statements.into_iter().map(|(node, _)| node).collect(), ty)
+    }
+
+    /// Warns about the values computed for nothing in a block, only its last statement giving
+    /// the block its value.
+    fn dropped_values(&mut self, block: &[(AyNode<Statement>, AyType)]) {
+        let Some((_, init)) = block.split_last() else {
+            return;
+        };
+
+        for (node, _) in init {
+            let Statement::Expr(expr) = &node.inner else {
+                continue;
+            };
+            if !pure(expr) {
+                continue;
+            }
+
+            self.warnings.push((
+                Lint::UnusedValue,
+                Error::from_span(node.span.clone(), &message!("lint.unused_value"))
+                    .with_label(&message!("lint.unused_value.label")),
+            ));
         }
-        BExpr::Negated(expr) => {
-            let expr = convert_expr(expr)?;
+    }
+
+    /// Types a statement, returning it along with the type of the value it evaluates to.
+    fn statement(
+        &mut self,
+        AyNode { span, inner }: &AyNode<BStatement>,
+    ) -> (AyNode<Statement>, AyType) {
+        let (inner, ty) = match inner {
+            BStatement::FunDec(binding::FunDec { name, args, body }) => {
+                let arg_types = args.iter().map(|_| self.fresh()).collect::<Vec<_>>();
+                let result = self.fresh();
+                let fun_type = AyType::Function {
+                    args: arg_types.clone(),
+                    result: Box::new(result.clone()),
+                };
+
+                // Monomorphic while typing its own body to allow recursion
+                self.funs.define(
+                    name.clone(),
+                    Scheme {
+                        generics: vec![],
+                        body: fun_type.clone(),
+                    },
+                );
+
+                self.vars.push_layer();
+                args.iter()
+                    .zip(arg_types)
+                    .for_each(|(arg, ty)| self.vars.define(arg.clone(), ty));
+                let (body, body_type) = self.block(body);
+                self.vars.pop_layer();
+
+                self.expect(span, &result, &body_type, |err| {
+                    err.with_note(&message!("AY0201.return"))
+                });
+
+                let scheme = self.generalize(&fun_type);
+                self.funs.define(name.clone(), scheme);
+
+                (
+                    Statement::FunDec(FunDec {
+                        name: name.clone(),
+                        args: args.clone(),
+                        fun_type,
+                        body,
+                    }),
+                    AyType::Unit,
+                )
+            }
+            BStatement::VarDec(binding::VarDec { names, values }) => {
+                // Names are visible in their own values, like in binding
+                let types = names
+                    .iter()
+                    .map(|name| {
+                        let ty = self.fresh();
+                        self.vars.define(name.clone(), ty.clone());
+                        ty
+                    })
+                    .collect::<Vec<_>>();
+
+                let values = values
+                    .iter()
+                    .zip(types)
+                    .map(|(value, ty)| {
+                        let value = self.expr(value);
+                        self.expect(&value.span, &ty, &value.expr_type, |err| err);
+                        value
+                    })
+                    .collect::<Vec<TypedExpr>>();
+
+                (
+                    Statement::VarDec(VarDec {
+                        names: names.clone(),
+                        values,
+                    }),
+                    AyType::Unit,
+                )
+            }
+            BStatement::Expr(expr) => {
+                let expr = self.expr(expr);
+                let ty = expr.expr_type.clone();
+                (Statement::Expr(expr), ty)
+            }
+            BStatement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let cond = self.expr(cond);
+                let (then, then_type) = self.block(then);
+                let (otherwise, otherwise_type) = self.block(otherwise);
+
+                // Branches of different types are allowed, the value is then unusable
+                let snapshot = self.substitution.clone();
+                let ty = if !otherwise.is_empty() && self.unify(&then_type, &otherwise_type) {
+                    then_type
+                } else {
+                    self.substitution = snapshot;
+                    AyType::Unit
+                };
+
+                (
+                    Statement::If {
+                        cond,
+                        then,
+                        otherwise,
+                    },
+                    ty,
+                )
+            }
+            BStatement::Loop { cond, body } => (
+                Statement::Loop {
+                    cond: cond.as_ref().map(|cond| self.expr(cond)),
+                    body: self.block(body).0,
+                },
+                AyType::Unit,
+            ),
+            BStatement::Test { name, body } => (
+                Statement::Test {
+                    name: name.clone(),
+                    body: self.block(body).0,
+                },
+                AyType::Unit,
+            ),
+        };
+
+        (
+            AyNode {
+                span: span.clone(),
+                inner,
+            },
+            ty,
+        )
+    }
+
+    fn expr(&mut self, AyNode { span, inner }: &AyNode<BExpr>) -> TypedExpr {
+        let (expr_type, inner) = match inner {
+            BExpr::Number(number) => (AyType::Number, Expr::Number(*number)),
+            BExpr::String(string) => (AyType::String, Expr::String(string.clone())),
+            BExpr::Var(name) => match self.vars.get(name).cloned() {
+                Some(ty) => (ty, Expr::Var(name.clone())),
+                None => self.placeholder(error(
+                    span,
+                    BindingError::UndefinedVariable {
+                        name: name.clone(),
+                        candidates: vec![],
+                    },
+                )),
+            },
+            // Already reported while binding
+            BExpr::Error => (self.fresh(), Expr::Error),
+            BExpr::Array { items } => {
+                let item_type = self.fresh();
+                let items = items
+                    .iter()
+                    .map(|item| {
+                        let item = self.expr(item);
+                        self.expect(&item.span, &item_type, &item.expr_type, |err| {
+                            err.with_secondary_label(
+                                items[0].span.clone(),
+                                &message!("AY0201.first_item"),
+                            )
+                            .with_note(&message!("AY0201.array"))
+                        });
+                        item
+                    })
+                    .collect::<Vec<TypedExpr>>();
+
+                (AyType::Array(Box::new(item_type)), Expr::Array { items })
+            }
+            BExpr::Negated(expr) => {
+                let expr = self.expr(expr);
+
+                // Numbers negate to 0 or 1 and arrays and booleans to a Bool. Without a way to
+                // spell "any of these", an operand of unknown type, such as a parameter, is taken
+                // to be a Bool, which keeps the negation's type the one it has when run
+                match self.resolve(&expr.expr_type) {
+                    AyType::Number => (AyType::Number, Expr::Negated(Box::new(expr))),
+                    ty @ AyType::Var(_) => {
+                        self.unify(&ty, &AyType::Bool);
+                        (AyType::Bool, Expr::Negated(Box::new(expr)))
+                    }
+                    AyType::Array(_) | AyType::Bool => {
+                        (AyType::Bool, Expr::Negated(Box::new(expr)))
+                    }
+                    other => {
+                        self.placeholder(error(span, TypingError::InvalidNegation { found: other }))
+                    }
+                }
+            }
+            BExpr::Comparison {
+                left,
+                right,
+                operator,
+            } => {
+                let left = self.expr(left);
+                let right = self.expr(right);
+
+                if !self.unify(&left.expr_type, &right.expr_type) {
+                    let (left_type, right_type) = (
+                        self.resolve(&left.expr_type),
+                        self.resolve(&right.expr_type),
+                    );
 
-            Ok(TypedExpr {
-                expr_type: match expr.expr_type {
-                    AyType::Number => Ok(AyType::Number),
-                    AyType::Array(_) => Ok(AyType::Bool),
-                    _ => Err(Trace::new(
-                        Stage::Binding,
-                        Error::from_span(
+                    // Still a comparison, whose value is known to be a boolean
+                    self.errors.push(Trace::new(
+                        Stage::Typing,
+                        Error::new(
                             span.clone(),
-                            format!("Can only negate Number or Array, not {:?}", expr.expr_type)
-                                .as_ref(),
+                            TypingError::Incomparable {
+                                left: left_type.clone(),
+                                right: right_type.clone(),
+                            },
+                        )
+                        .with_secondary_label(
+                            left.span.clone(),
+                            &message!("AY0202.left", left_type),
+                        )
+                        .with_secondary_label(
+                            right.span.clone(),
+                            &message!("AY0202.right", right_type),
                         ),
-                    )),
-                }?,
-                inner: todo!(),
-            })
-        }
-        BExpr::Comparison {
-            left,
-            right,
-            operator,
-        } => {
-            let left = convert_expr(left)?;
-            let right = convert_expr(right)?;
-
-            if left.expr_type == right.expr_type {
-                Ok(TypedExpr {
-                    expr_type: AyType::Bool,
-                    inner: Expr::Comparison {
+                    ));
+                }
+
+                (
+                    AyType::Bool,
+                    Expr::Comparison {
                         left: Box::new(left),
                         right: Box::new(right),
                         operator: operator.clone(),
                     },
+                )
+            }
+            BExpr::FunCall { tense, name, args } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(arg))
+                    .collect::<Vec<TypedExpr>>();
+
+                let scheme = match_function(name, &self.funs)
+                    .and_then(|(key, _)| self.funs.get(key))
+                    .cloned();
+                let fun_type = match scheme {
+                    Some(scheme) => self.instantiate(&scheme),
+                    None => self.builtin(span, name, &args),
+                };
+
+                self.call(span, tense, name, fun_type, args)
+            }
+        };
+
+        TypedExpr {
+            span: span.clone(),
+            expr_type,
+            inner,
+        }
+    }
+
+    /// Types a call to a function of type `fun_type` with already typed arguments.
+    fn call(
+        &mut self,
+        span: &Span,
+        tense: &Tense,
+        name: &str,
+        fun_type: AyType,
+        args: Vec<TypedExpr>,
+    ) -> (AyType, Expr) {
+        let AyType::Function {
+            args: arg_types,
+            result,
+        } = self.resolve(&fun_type)
+        else {
+            let found = self.resolve(&fun_type);
+            return self.placeholder(error(
+                span,
+                TypingError::NotAFunction {
+                    name: name.to_owned(),
+                    found,
+                },
+            ));
+        };
+
+        let ty = self.resolve(&fun_type);
+        let signature = message!("signature", name, ty);
+
+        if arg_types.len() != args.len() {
+            return self.placeholder(Trace::new(
+                Stage::Typing,
+                Error::new(
+                    span.clone(),
+                    TypingError::ArgumentCount {
+                        function: name.to_owned(),
+                        expected: arg_types.len(),
+                        found: args.len(),
+                    },
+                )
+                .with_note(&signature),
+            ));
+        }
+
+        for (arg, ty) in args.iter().zip(arg_types.iter()) {
+            self.expect(&arg.span, ty, &arg.expr_type, |err| {
+                err.with_note(&signature)
+            });
+        }
+
+        (
+            *result,
+            Expr::FunCall {
+                tense: tense.clone(),
+                name: name.to_owned(),
+                fun_type,
+                args,
+            },
+        )
+    }
+
+    /// Builtins are variadic, so their type is built from the arguments of each call.
+    fn builtin(&mut self, span: &Span, name: &str, args: &[TypedExpr]) -> AyType {
+        let arg_types = args
+            .iter()
+            .map(|arg| arg.expr_type.clone())
+            .collect::<Vec<_>>();
+
+        let same = |typer: &mut Self, ty: &AyType| {
+            arg_types.iter().zip(args).for_each(|(arg_type, arg)| {
+                typer.expect(&arg.span, ty, arg_type, |err| {
+                    err.with_note(&message!("AY0201.same_arguments", name))
                 })
-            } else {
-                Err(Trace::new(
-                    Stage::Binding,
-                    Error::from_span(
-                        span.clone(),
-                        format!(
-                            "Cannot compare {:?} and {:?}",
-                            left.expr_type, right.expr_type
-                        )
-                        .as_ref(),
-                    ),
-                ))
+            })
+        };
+
+        let result = match name {
+            "wìntxu" => AyType::Unit,
+            "sung" => {
+                let ty = arg_types.first().cloned().unwrap_or(AyType::Number);
+                same(self, &ty);
+                ty
+            }
+            "luke" | "'ì'awnyu" => {
+                same(self, &AyType::Number);
+                AyType::Number
+            }
+            "teng" => {
+                let ty = self.fresh();
+                same(self, &ty);
+                AyType::Bool
+            }
+            "nìngay" => {
+                same(self, &AyType::Bool);
+                AyType::Unit
+            }
+            _ => {
+                // Returning anything, so that the call causes no other error
+                self.errors.push(error(
+                    span,
+                    BindingError::UndefinedFunction {
+                        name: name.to_owned(),
+                        candidates: vec![],
+                    },
+                ));
+                self.fresh()
             }
+        };
+
+        AyType::Function {
+            args: arg_types,
+            result: Box::new(result),
         }
-        _ => todo!(),
+    }
+
+    fn resolve_statement(&self, statement: &mut Statement) {
+        match statement {
+            Statement::FunDec(FunDec { fun_type, body, .. }) => {
+                *fun_type = self.resolve(fun_type);
+                body.iter_mut()
+                    .for_each(|node| self.resolve_statement(&mut node.inner));
+            }
+            Statement::VarDec(VarDec { values, .. }) => {
+                values.iter_mut().for_each(|value| self.resolve_expr(value))
+            }
+            Statement::Expr(expr) => self.resolve_expr(expr),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                self.resolve_expr(cond);
+                then.iter_mut()
+                    .chain(otherwise.iter_mut())
+                    .for_each(|node| self.resolve_statement(&mut node.inner));
+            }
+            Statement::Loop { cond, body } => {
+                cond.iter_mut().for_each(|cond| self.resolve_expr(cond));
+                body.iter_mut()
+                    .for_each(|node| self.resolve_statement(&mut node.inner));
+            }
+            Statement::Test { body, .. } => body
+                .iter_mut()
+                .for_each(|node| self.resolve_statement(&mut node.inner)),
+        }
+    }
+
+    fn resolve_expr(&self, expr: &mut TypedExpr) {
+        expr.expr_type = self.resolve(&expr.expr_type);
+
+        match &mut expr.inner {
+            Expr::FunCall { fun_type, args, .. } => {
+                *fun_type = self.resolve(fun_type);
+                args.iter_mut().for_each(|arg| self.resolve_expr(arg));
+            }
+            Expr::Array { items } => items.iter_mut().for_each(|item| self.resolve_expr(item)),
+            Expr::Comparison { left, right, .. } => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            Expr::Negated(expr) => self.resolve_expr(expr),
+            Expr::Number(_) | Expr::String(_) | Expr::Var(_) | Expr::Error => {}
+        }
+    }
+}
+
+/// Whether evaluating `expr` has no effect besides giving its value, calls being assumed to have
+/// some.
+fn pure(expr: &TypedExpr) -> bool {
+    match &expr.inner {
+        Expr::Number(_) | Expr::String(_) | Expr::Var(_) => true,
+        Expr::Array { items } => items.iter().all(pure),
+        Expr::Comparison { left, right, .. } => pure(left) && pure(right),
+        Expr::Negated(expr) => pure(expr),
+        // Already reported
+        Expr::FunCall { .. } | Expr::Error => false,
+    }
+}
+
+fn error(span: &Span, kind: impl Into<ErrorKind>) -> Trace {
+    Trace::new(Stage::Typing, Error::new(span.clone(), kind))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::parsing,
+        error::kind::{ErrorKind, TypingError},
+    };
+
+    #[test]
+    fn test_every_error() {
+        let source = "ngop 'u a alu sung fa 1 sì san 2 sìk.\n\
+                      sung fa a sì 3.\n\
+                      ke san kaltxì sìk.\n";
+        let ast = binding::convert(&parsing::parse_content(None, source).unwrap()).unwrap();
+        let trace = convert(&ast).unwrap_err();
+
+        // The value of `a` is still a number, the second line is fine
+        assert_eq!(
+            trace.traces().map(Trace::kind).collect::<Vec<_>>(),
+            [
+                Some(&ErrorKind::Typing(TypingError::Mismatch {
+                    expected: AyType::Number,
+                    found: AyType::String,
+                })),
+                Some(&ErrorKind::Typing(TypingError::InvalidNegation {
+                    found: AyType::String,
+                })),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_negation_of_unknown_operand() {
+        let types = |source: &str| {
+            let ast = binding::convert(&parsing::parse_content(None, source).unwrap()).unwrap();
+            convert(&ast)
+                .map(|typed| match &typed[1].inner {
+                    Statement::VarDec(VarDec { values, .. }) => values[0].expr_type.clone(),
+                    other => panic!("{other:?}"),
+                })
+                .map_err(|trace| trace.kind().cloned())
+        };
+
+        // A negated parameter is a Bool, whatever the function is called with
+        assert_eq!(
+            types(
+                "ngop lì'ukìng f fa a alu ke a txew.\n\
+                 ngop 'u b alu f fa may' 1 sì 1 livu teng.\n"
+            ),
+            Ok(AyType::Bool)
+        );
+        assert_eq!(
+            types(
+                "ngop lì'ukìng f fa a alu ke a txew.\n\
+                 ngop 'u b alu f fa san x sìk.\n"
+            ),
+            Err(Some(ErrorKind::Typing(TypingError::Mismatch {
+                expected: AyType::Bool,
+                found: AyType::String,
+            })))
+        );
+        // Arrays negate to a Bool, so the result cannot be used as an array
+        assert_eq!(
+            types("ngop 'u a alu 1.\nngop 'u b alu ke vezeykoyu a.\n"),
+            Ok(AyType::Bool)
+        );
+        assert_eq!(
+            types(
+                "ngop lì'ukìng f fa a alu ke a txew.\n\
+                 ngop 'u b alu f fa vezeykoyu 1.\n"
+            ),
+            Err(Some(ErrorKind::Typing(TypingError::Mismatch {
+                expected: AyType::Bool,
+                found: AyType::Array(Box::new(AyType::Number)),
+            })))
+        );
     }
 }
@@ -0,0 +1,170 @@
+use crate::{
+    ast::{binding, cst, lib::SourceCode, parsing, typing},
+    error::trace::Trace,
+};
+
+use {
+    clap::ValueEnum,
+    serde::Serialize,
+    serde_json::{Map, Value},
+};
+
+/// Compiler stage whose output should be emitted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum EmitStage {
+    /// Lossless concrete syntax tree
+    Cst,
+    /// Parsed AST
+    Ast,
+    /// Bound AST
+    Bound,
+    /// Typed AST
+    Typed,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum EmitFormat {
+    #[default]
+    Json,
+    Sexpr,
+}
+
+pub fn emit(source: SourceCode, stage: EmitStage, format: EmitFormat) -> Result<String, Trace> {
+    let value = match stage {
+        EmitStage::Cst => {
+            let (_, content) = parsing::read_source(source)?;
+            to_value(cst::parse(content.as_ref())?)
+        }
+        EmitStage::Ast => to_value(parsing::parse(source)?),
+        EmitStage::Bound => to_value(binding::convert(&parsing::parse(source)?)?),
+        EmitStage::Typed => to_value(typing::convert(&binding::convert(&parsing::parse(
+            source,
+        )?)?)?),
+    };
+
+    Ok(match format {
+        EmitFormat::Json => serde_json::to_string_pretty(&value)
+            .expect("JSON values can always be turned into a string"),
+        EmitFormat::Sexpr => to_sexpr(&value, 0),
+    })
+}
+
+fn to_value<T: Serialize>(node: T) -> Value {
+    // Every map key in the AST is a field name, which is the only way this could fail
+    serde_json::to_value(node).expect("AST nodes only have string keys")
+}
+
+const SEXPR_WIDTH: usize = 80;
+
+/// Renders a JSON value as an S-expression, breaking lists that do not fit on one line.
+///
+/// Enum variants (single capitalized key objects) become `(Variant ...)`, other objects become
+/// keyword lists `(:key value ...)` and arrays become plain lists.
+pub fn to_sexpr(value: &Value, depth: usize) -> String {
+    let flat = render(value, None);
+
+    if flat.len() + depth * 2 <= SEXPR_WIDTH {
+        flat
+    } else {
+        render(value, Some(depth))
+    }
+}
+
+/// Renders on a single line when `depth` is `None`, one item per line otherwise.
+fn render(value: &Value, depth: Option<usize>) -> String {
+    let (head, items) = match value {
+        Value::Null => return "nil".to_owned(),
+        Value::Bool(boolean) => return (if *boolean { "#t" } else { "#f" }).to_owned(),
+        Value::Number(number) => return number.to_string(),
+        Value::String(string) => return format!("{string:?}"),
+        Value::Array(items) => (None, positional_items(items)),
+        Value::Object(map) => match variant(map) {
+            Some((name, Value::Object(fields))) => (Some(name), keyword_items(fields)),
+            Some((name, Value::Array(items))) => (Some(name), positional_items(items)),
+            Some((name, inner)) => (Some(name), vec![(None, inner)]),
+            None => (None, keyword_items(map)),
+        },
+    };
+
+    let child = |item: &Value| match depth {
+        Some(depth) => to_sexpr(item, depth + 1),
+        None => render(item, None),
+    };
+
+    let separator = match depth {
+        Some(depth) => format!("\n{}", "  ".repeat(depth + 1)),
+        None => " ".to_owned(),
+    };
+
+    let body = head
+        .map(|head| head.to_string())
+        .into_iter()
+        .chain(items.iter().map(|(key, item)| match key {
+            Some(key) => format!(":{key} {}", child(item)),
+            None => child(item),
+        }))
+        .collect::<Vec<String>>()
+        .join(&separator);
+
+    format!("({body})")
+}
+
+fn variant(map: &Map<String, Value>) -> Option<(&String, &Value)> {
+    map.iter()
+        .next()
+        .filter(|(key, _)| map.len() == 1 && key.starts_with(char::is_uppercase))
+}
+
+fn positional_items(items: &[Value]) -> Vec<(Option<&String>, &Value)> {
+    items.iter().map(|item| (None, item)).collect()
+}
+
+fn keyword_items(map: &Map<String, Value>) -> Vec<(Option<&String>, &Value)> {
+    map.iter().map(|(key, value)| (Some(key), value)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sexpr_variants() {
+        let ast = parsing::parse(SourceCode::Content("ke 1.".to_owned())).unwrap();
+
+        assert_eq!(
+            to_sexpr(&to_value(&ast[0].inner), 0),
+            [
+                "(Expr",
+                "  :span (:start (1 1) :end (1 5) :range (0 4) :text \"ke 1\")",
+                "  :inner (Negated",
+                "    :span (:start (1 4) :end (1 5) :range (3 4) :text \"1\")",
+                "    :inner (Number 1)))",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_every_stage_of_examples() {
+        let mut dirs = vec![std::path::PathBuf::from("./examples")];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                    continue;
+                }
+                if path.extension().is_none_or(|ext| ext != "ay") {
+                    continue;
+                }
+
+                // Each stage either gives its tree or an error for it, without panicking
+                let invalid = path.components().any(|dir| dir.as_os_str() == "invalid");
+                let source = SourceCode::File(path.to_string_lossy().into_owned());
+                let typed = emit(source, EmitStage::Typed, EmitFormat::Sexpr);
+                assert_eq!(typed.is_err(), invalid, "{}", path.display());
+            }
+        }
+    }
+}
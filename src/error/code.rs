@@ -0,0 +1,149 @@
+use std::{fmt::Display, str::FromStr};
+
+/// Declares every error code along with its title and the file explaining it.
+///
+/// Codes are stable: once released, a code keeps its meaning and is never reused. The hundreds
+/// tell the stage: `AY00xx` parsing, `AY01xx` binding, `AY02xx` typing, `AY03xx` running and
+/// `AY04xx` tooling.
+macro_rules! codes {
+    ($($variant:ident = $code:literal : $title:literal),* $(,)?) => {
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Code {
+            $($variant),*
+        }
+
+        impl Code {
+            pub const ALL: &'static [Code] = &[$(Code::$variant),*];
+
+            /// Code as printed, e.g. `AY0102`
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Code::$variant => $code),*
+                }
+            }
+
+            /// Short description, e.g. `undefined function`
+            pub fn title(self) -> &'static str {
+                match self {
+                    $(Code::$variant => $title),*
+                }
+            }
+
+            /// Long-form Markdown explanation, with an example program when the error can be
+            /// triggered by one
+            pub fn explanation(self) -> &'static str {
+                match self {
+                    $(Code::$variant => include_str!(concat!("explanations/", $code, ".md"))),*
+                }
+            }
+        }
+    };
+}
+
+codes! {
+    Syntax = "AY0001": "syntax error",
+    InvalidNumber = "AY0002": "invalid number",
+    DeclarationMismatch = "AY0003": "mismatched declaration",
+    UnsupportedConstruct = "AY0004": "unsupported construct",
+    ImportWithoutPath = "AY0005": "import without a file path",
+    UnreadableModule = "AY0006": "unreadable module",
+    NestingTooDeep = "AY0007": "nesting too deep",
+    CyclicImport = "AY0008": "cyclic import",
+
+    UndefinedVariable = "AY0101": "undefined variable",
+    UndefinedFunction = "AY0102": "undefined function",
+
+    TypeMismatch = "AY0201": "type mismatch",
+    IncomparableTypes = "AY0202": "incomparable types",
+    InvalidNegation = "AY0203": "invalid negation",
+    NotAFunction = "AY0204": "not a function",
+    WrongArgumentCount = "AY0205": "wrong number of arguments",
+
+    InvalidOperand = "AY0301": "invalid operand",
+    WrongArgumentCountAtRuntime = "AY0302": "wrong number of arguments at runtime",
+    DivisionByZero = "AY0303": "division by zero",
+    OutputFailure = "AY0304": "output failure",
+    AssertionFailed = "AY0305": "assertion failed",
+    UnboundCode = "AY0306": "unbound code",
+
+    FormattingFailure = "AY0401": "formatting failure",
+    RewriteFailure = "AY0402": "rewrite failure",
+}
+
+impl Display for Code {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Code {
+    type Err = String;
+
+    /// Parses a code, ignoring case and the `AY` prefix: `AY0102`, `ay0102` and `0102` match.
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let digits = code.trim().to_uppercase();
+        let digits = digits.strip_prefix("AY").unwrap_or(&digits);
+
+        Code::ALL
+            .iter()
+            .find(|known| known.as_str()[2..] == *digits)
+            .copied()
+            .ok_or_else(|| format!("Unknown error code `{code}`"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{binding, parsing, typing},
+        error::trace::Trace,
+        interpreter::runtime::Interpreter,
+    };
+
+    /// Code of the deepest error raised by `source`, typing it for typing codes and running it
+    /// otherwise.
+    fn raised(source: &str, typed: bool) -> Option<Code> {
+        let code = |trace: Trace| trace.errors().next().and_then(|(_, err)| err.code());
+
+        let ast = match parsing::parse_content(None, source) {
+            Ok(ast) => ast,
+            Err(trace) => return code(trace),
+        };
+        let bound = match binding::convert(&ast) {
+            Ok(bound) => bound,
+            Err(trace) => return code(trace),
+        };
+
+        if typed {
+            typing::convert(&bound).err().and_then(code)
+        } else {
+            Interpreter::with_output(Box::new(std::io::sink()))
+                .run(&bound)
+                .err()
+                .and_then(code)
+        }
+    }
+
+    #[test]
+    fn test_explanations() {
+        for code in Code::ALL {
+            assert!(code
+                .explanation()
+                .starts_with(&format!("# {code}: {}\n", code.title())));
+            assert_eq!(code.as_str().parse::<Code>(), Ok(*code));
+
+            // The first example of an explanation is the erroneous one
+            let Some((_, example)) = code.explanation().split_once("```aysinvi\n") else {
+                continue;
+            };
+            let example = &example[..example.find("```").unwrap()];
+
+            assert_eq!(
+                raised(example, code.as_str().starts_with("AY02")),
+                Some(*code),
+                "example of {code}"
+            );
+        }
+    }
+}
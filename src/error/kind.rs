@@ -0,0 +1,433 @@
+use super::{
+    code::Code,
+    locale::{self, either, message, Locale},
+};
+
+use crate::ast::{lib::AyType, parsing::Rule};
+
+use std::fmt::Display;
+
+use pest::error::ErrorVariant;
+
+/// What went wrong, independently of where, for callers to match on rather than on messages
+///
+/// Failures are grouped by the stage they are detected at, though a later stage may report one
+/// of an earlier stage, e.g. running an unbound call raises [`BindingError::UndefinedFunction`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Parsing(ParsingError),
+    Binding(BindingError),
+    Typing(TypingError),
+    Running(RunningError),
+    Formatting(FormattingError),
+    Rewriting(RewritingError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsingError {
+    /// Source not following the grammar
+    Syntax {
+        expected: Vec<Rule>,
+        unexpected: Vec<Rule>,
+    },
+    /// Number literal too large to be represented
+    InvalidNumber {
+        literal: String,
+    },
+    /// Declaration with more names than values or the other way around
+    DeclarationMismatch {
+        names: usize,
+        values: usize,
+    },
+    /// Construct accepted by the grammar but not handled by the compiler
+    Unsupported {
+        construct: String,
+    },
+    /// `sar` import in code that was not read from a file
+    ImportWithoutPath {
+        module: String,
+    },
+    UnreadableModule {
+        path: String,
+        reason: String,
+    },
+    /// Rules nested deeper than the compiler can walk
+    TooDeep {
+        limit: usize,
+    },
+    /// `sar` import of a file that is still being imported, each file importing the next one
+    CyclicImport {
+        path: String,
+        cycle: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingError {
+    /// Unknown variable, along with the declared names it may be a typo of, closest first
+    UndefinedVariable {
+        name: String,
+        candidates: Vec<String>,
+    },
+    /// Unknown function, along with the declared names it may be a typo of, closest first
+    UndefinedFunction {
+        name: String,
+        candidates: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypingError {
+    Mismatch {
+        expected: AyType,
+        found: AyType,
+    },
+    Incomparable {
+        left: AyType,
+        right: AyType,
+    },
+    InvalidNegation {
+        found: AyType,
+    },
+    NotAFunction {
+        name: String,
+        found: AyType,
+    },
+    ArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+/// Failures of a running program, types being those of the values it handles
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunningError {
+    CannotAdd {
+        left: &'static str,
+        right: &'static str,
+    },
+    CannotNegate {
+        found: &'static str,
+    },
+    NonNumberArgument {
+        function: String,
+        found: &'static str,
+    },
+    /// Call with a number of arguments the function cannot take, `expected` being `None` for
+    /// builtins taking a variable number of them
+    ArgumentCount {
+        function: String,
+        expected: Option<usize>,
+        found: usize,
+    },
+    DivisionByZero,
+    Output {
+        reason: String,
+    },
+    /// `nìngay` called with a false condition
+    AssertionFailed,
+    /// Placeholder left by an expression that failed to bind
+    Unbound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormattingError {
+    /// Formatted code that does not parse
+    Unparsable,
+    /// Formatted code parsing to another program
+    MeaningChanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RewritingError {
+    /// Template building something that is not a node of the AST rewritten, written as a term
+    Unbuildable { term: String },
+}
+
+impl ErrorKind {
+    pub fn code(&self) -> Code {
+        match self {
+            ErrorKind::Parsing(err) => match err {
+                ParsingError::Syntax { .. } => Code::Syntax,
+                ParsingError::InvalidNumber { .. } => Code::InvalidNumber,
+                ParsingError::DeclarationMismatch { .. } => Code::DeclarationMismatch,
+                ParsingError::Unsupported { .. } => Code::UnsupportedConstruct,
+                ParsingError::ImportWithoutPath { .. } => Code::ImportWithoutPath,
+                ParsingError::UnreadableModule { .. } => Code::UnreadableModule,
+                ParsingError::TooDeep { .. } => Code::NestingTooDeep,
+                ParsingError::CyclicImport { .. } => Code::CyclicImport,
+            },
+            ErrorKind::Binding(err) => match err {
+                BindingError::UndefinedVariable { .. } => Code::UndefinedVariable,
+                BindingError::UndefinedFunction { .. } => Code::UndefinedFunction,
+            },
+            ErrorKind::Typing(err) => match err {
+                TypingError::Mismatch { .. } => Code::TypeMismatch,
+                TypingError::Incomparable { .. } => Code::IncomparableTypes,
+                TypingError::InvalidNegation { .. } => Code::InvalidNegation,
+                TypingError::NotAFunction { .. } => Code::NotAFunction,
+                TypingError::ArgumentCount { .. } => Code::WrongArgumentCount,
+            },
+            ErrorKind::Running(err) => match err {
+                RunningError::CannotAdd { .. }
+                | RunningError::CannotNegate { .. }
+                | RunningError::NonNumberArgument { .. } => Code::InvalidOperand,
+                RunningError::ArgumentCount { .. } => Code::WrongArgumentCountAtRuntime,
+                RunningError::DivisionByZero => Code::DivisionByZero,
+                RunningError::Output { .. } => Code::OutputFailure,
+                RunningError::AssertionFailed => Code::AssertionFailed,
+                RunningError::Unbound => Code::UnboundCode,
+            },
+            ErrorKind::Formatting(_) => Code::FormattingFailure,
+            ErrorKind::Rewriting(_) => Code::RewriteFailure,
+        }
+    }
+}
+
+impl ParsingError {
+    /// Syntax error of a pest error variant, without rules for custom messages.
+    pub fn syntax(variant: &ErrorVariant<Rule>) -> Self {
+        match variant {
+            ErrorVariant::ParsingError {
+                positives,
+                negatives,
+            } => ParsingError::Syntax {
+                expected: positives.clone(),
+                unexpected: negatives.clone(),
+            },
+            ErrorVariant::CustomError { .. } => ParsingError::Syntax {
+                expected: vec![],
+                unexpected: vec![],
+            },
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message(locale::current()))
+    }
+}
+
+impl ErrorKind {
+    /// Description of the error in `locale`, as printed
+    pub fn message(&self, locale: Locale) -> String {
+        match self {
+            ErrorKind::Parsing(err) => match err {
+                ParsingError::Syntax {
+                    expected,
+                    unexpected,
+                } => {
+                    let rules = |rules: &[Rule]| {
+                        let mut descriptions = vec![];
+                        for rule in rules {
+                            let description = describe(*rule, locale);
+                            if !descriptions.contains(&description) {
+                                descriptions.push(description);
+                            }
+                        }
+                        either(locale, &descriptions)
+                    };
+                    let (expected, unexpected) = (rules(expected), rules(unexpected));
+
+                    match (expected.is_empty(), unexpected.is_empty()) {
+                        (false, true) => {
+                            message!(in locale, "AY0001.expected", expected, unexpected)
+                        }
+                        (true, false) => {
+                            message!(in locale, "AY0001.unexpected", expected, unexpected)
+                        }
+                        (false, false) => message!(in locale, "AY0001.both", expected, unexpected),
+                        (true, true) => message!(in locale, "AY0001.unknown", expected, unexpected),
+                    }
+                }
+                ParsingError::InvalidNumber { literal } => {
+                    message!(in locale, "AY0002", literal)
+                }
+                ParsingError::DeclarationMismatch { names, values } => {
+                    message!(in locale, "AY0003", names, values)
+                }
+                ParsingError::Unsupported { construct } => {
+                    message!(in locale, "AY0004", construct)
+                }
+                ParsingError::ImportWithoutPath { module } => {
+                    message!(in locale, "AY0005", module)
+                }
+                ParsingError::UnreadableModule { path, reason } => {
+                    message!(in locale, "AY0006", path, reason)
+                }
+                ParsingError::TooDeep { limit } => message!(in locale, "AY0007", limit),
+                ParsingError::CyclicImport { path, cycle } => {
+                    let cycle = cycle.join(" -> ");
+                    message!(in locale, "AY0008", path, cycle)
+                }
+            },
+            ErrorKind::Binding(err) => match err {
+                BindingError::UndefinedVariable { name, .. } => {
+                    message!(in locale, "AY0101", name)
+                }
+                BindingError::UndefinedFunction { name, .. } => {
+                    message!(in locale, "AY0102", name)
+                }
+            },
+            ErrorKind::Typing(err) => match err {
+                TypingError::Mismatch { expected, found } => {
+                    message!(in locale, "AY0201", expected, found)
+                }
+                TypingError::Incomparable { left, right } => {
+                    message!(in locale, "AY0202", left, right)
+                }
+                TypingError::InvalidNegation { found } => message!(in locale, "AY0203", found),
+                TypingError::NotAFunction { name, .. } => message!(in locale, "AY0204", name),
+                TypingError::ArgumentCount {
+                    function,
+                    expected,
+                    found,
+                } => message!(in locale, "AY0205", function, expected, found),
+            },
+            ErrorKind::Running(err) => match err {
+                RunningError::CannotAdd { left, right } => {
+                    message!(in locale, "AY0301.add", left, right)
+                }
+                RunningError::CannotNegate { found } => {
+                    message!(in locale, "AY0301.negate", found)
+                }
+                RunningError::NonNumberArgument { function, found } => {
+                    message!(in locale, "AY0301.argument", function, found)
+                }
+                RunningError::ArgumentCount {
+                    function,
+                    expected: Some(expected),
+                    found,
+                } => message!(in locale, "AY0302", function, expected, found),
+                RunningError::ArgumentCount {
+                    function,
+                    expected: None,
+                    found,
+                } => message!(in locale, "AY0302.variadic", function, found),
+                RunningError::DivisionByZero => message!(in locale, "AY0303"),
+                RunningError::Output { reason } => message!(in locale, "AY0304", reason),
+                RunningError::AssertionFailed => message!(in locale, "AY0305"),
+                RunningError::Unbound => message!(in locale, "AY0306"),
+            },
+            ErrorKind::Formatting(err) => match err {
+                FormattingError::Unparsable => message!(in locale, "AY0401.unparsable"),
+                FormattingError::MeaningChanged => message!(in locale, "AY0401.meaning"),
+            },
+            ErrorKind::Rewriting(err) => match err {
+                RewritingError::Unbuildable { term } => message!(in locale, "AY0402", term),
+            },
+        }
+    }
+}
+
+/// What a grammar rule matches, e.g. `a function call` for `fun_call`
+pub fn describe(rule: Rule, locale: Locale) -> String {
+    message!(in locale, &format!("rule.{rule:?}"))
+}
+
+macro_rules! display_stage_errors {
+    ($($error:ident),*) => {
+        $(
+            impl Display for $error {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    ErrorKind::from(self.clone()).fmt(f)
+                }
+            }
+        )*
+    };
+}
+
+display_stage_errors!(
+    ParsingError,
+    BindingError,
+    TypingError,
+    RunningError,
+    FormattingError,
+    RewritingError
+);
+
+impl std::error::Error for ErrorKind {}
+impl std::error::Error for ParsingError {}
+impl std::error::Error for BindingError {}
+impl std::error::Error for TypingError {}
+impl std::error::Error for RunningError {}
+impl std::error::Error for FormattingError {}
+impl std::error::Error for RewritingError {}
+
+macro_rules! from_stage_errors {
+    ($($stage:ident($error:ident)),*) => {
+        $(
+            impl From<$error> for ErrorKind {
+                fn from(err: $error) -> Self {
+                    ErrorKind::$stage(err)
+                }
+            }
+        )*
+    };
+}
+
+from_stage_errors!(
+    Parsing(ParsingError),
+    Binding(BindingError),
+    Typing(TypingError),
+    Running(RunningError),
+    Formatting(FormattingError),
+    Rewriting(RewritingError)
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{binding, parsing, typing},
+        interpreter::runtime::Interpreter,
+    };
+
+    use std::error::Error as _;
+
+    #[test]
+    fn test_kinds() {
+        let ast = parsing::parse_content(None, "ngop lì'ukìng t.aron fa a alu a.\ntìyarom fa 1.")
+            .unwrap();
+        let trace = binding::convert(&ast).unwrap_err();
+        assert_eq!(
+            trace.kind(),
+            Some(&ErrorKind::Binding(BindingError::UndefinedFunction {
+                name: "tìyarom".to_owned(),
+                candidates: vec!["tìyaron".to_owned(), "tayaron".to_owned()],
+            }))
+        );
+        assert_eq!(
+            trace.source().map(ToString::to_string),
+            Some("Undefined function: 'tìyarom'".to_owned())
+        );
+
+        let ast = parsing::parse_content(None, "sung fa 1 sì san 2 sìk.").unwrap();
+        let bound = binding::convert(&ast).unwrap();
+        assert_eq!(
+            typing::convert(&bound).unwrap_err().kind(),
+            Some(&ErrorKind::Typing(TypingError::Mismatch {
+                expected: AyType::Number,
+                found: AyType::String,
+            }))
+        );
+        assert_eq!(
+            Interpreter::with_output(Box::new(std::io::sink()))
+                .run(&bound)
+                .unwrap_err()
+                .kind(),
+            Some(&ErrorKind::Running(RunningError::CannotAdd {
+                left: "Number",
+                right: "String",
+            }))
+        );
+
+        let trace = parsing::parse_content(None, "ngop 'u a alu.").unwrap_err();
+        assert!(matches!(
+            trace.kind(),
+            Some(ErrorKind::Parsing(ParsingError::Syntax { expected, .. })) if !expected.is_empty()
+        ));
+    }
+}
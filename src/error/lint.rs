@@ -0,0 +1,280 @@
+use super::{
+    trace::{render, Stage, TraceError},
+    trace_error::Error,
+};
+
+use crate::{
+    ast::cst::{Cst, TokenKind},
+    highlight::render::{self, Role},
+};
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use clap::ValueEnum;
+
+/// Suspicious but legal code, reported without stopping the compilation unless denied
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Lint {
+    /// Variable or parameter declared but never read
+    UnusedVariable,
+    /// Declaration hiding another one of the same name
+    ShadowedName,
+    /// Statement after a `leyn` loop without condition, which never ends
+    UnreachableCode,
+    /// Expression without effect whose value is dropped, not being the last of its block
+    UnusedValue,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Level {
+    /// Not reported
+    Allow,
+    /// Reported without failing
+    #[default]
+    Warn,
+    /// Reported as an error, failing the command
+    Deny,
+}
+
+impl Lint {
+    /// Name used on the command line and in suppression comments, e.g. `unused_variable`
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused_variable",
+            Lint::ShadowedName => "shadowed_name",
+            Lint::UnreachableCode => "unreachable_code",
+            Lint::UnusedValue => "unused_value",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Warning {
+    pub lint: Lint,
+    /// File the warning is in, `None` for code not read from a file
+    pub file: Option<String>,
+    pub level: Level,
+    pub stage: Stage,
+    pub error: Error,
+}
+
+/// Collects the warnings of a compilation, dropping the allowed and suppressed ones
+///
+/// A lint is suppressed on a line by a `''allow: lint_name''` comment at its end, or on a line of
+/// its own just above. Several lints can be allowed at once by separating them with commas.
+#[derive(Debug, Default)]
+pub struct Warnings {
+    /// File currently being compiled, which the next suppressions and warnings belong to
+    pub file: Option<String>,
+    levels: BTreeMap<Lint, Level>,
+    /// Lints allowed on each line of each file
+    suppressed: BTreeMap<(Option<String>, usize), Vec<Lint>>,
+    list: Vec<Warning>,
+}
+
+impl Warnings {
+    pub fn new(levels: BTreeMap<Lint, Level>) -> Self {
+        Warnings {
+            levels,
+            ..Default::default()
+        }
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or_default()
+    }
+
+    /// Reads the suppression comments of the current file.
+    pub fn suppress_in(&mut self, cst: &Cst) {
+        let mut code_lines = vec![];
+        let mut comments = vec![];
+
+        for token in cst.tokens() {
+            let line = token.span.start_pos().line_col().0;
+            match token.kind {
+                TokenKind::Comment => comments.push((line, token.span.as_str())),
+                TokenKind::Whitespace => {}
+                _ => code_lines.push(line),
+            }
+        }
+
+        for (line, comment) in comments {
+            let Some(names) = comment
+                .trim_start_matches("''")
+                .trim_end_matches("''")
+                .trim()
+                .strip_prefix("allow:")
+            else {
+                continue;
+            };
+
+            // Comments alone on their line apply to the next one
+            let line = if code_lines.contains(&line) {
+                line
+            } else {
+                line + 1
+            };
+            self.suppressed
+                .entry((self.file.clone(), line))
+                .or_default()
+                .extend(
+                    names
+                        .split(',')
+                        .filter_map(|name| Lint::from_str(name.trim(), false).ok()),
+                );
+        }
+    }
+
+    pub fn warn(&mut self, stage: Stage, lint: Lint, error: Error) {
+        let line = match error.line_col() {
+            pest::error::LineColLocation::Pos((line, _))
+            | pest::error::LineColLocation::Span((line, _), _) => line,
+        };
+        let level = self.level(lint);
+        let suppressed = self
+            .suppressed
+            .get(&(self.file.clone(), line))
+            .is_some_and(|lints| lints.contains(&lint));
+
+        if level != Level::Allow && !suppressed {
+            self.list.push(Warning {
+                lint,
+                file: self.file.clone(),
+                level,
+                stage,
+                error,
+            });
+        }
+    }
+
+    pub fn list(&self) -> &[Warning] {
+        &self.list
+    }
+
+    /// Whether a denied lint was reported
+    pub fn denied(&self) -> bool {
+        self.list.iter().any(|warning| warning.level == Level::Deny)
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (severity, role) = match self.level {
+            Level::Deny => ("error", Role::Error),
+            _ => ("warning", Role::Warning),
+        };
+        let renderer = render::current();
+
+        write!(
+            f,
+            "{}\n{}",
+            renderer.paint(role, &format!("{severity}[{}]", self.lint.name())),
+            render(renderer, &self.stage, &self.error)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{binding, lib::SourceCode, parsing, typing};
+
+    fn lints(source: &str, levels: &[(Lint, Level)]) -> Vec<(Lint, String)> {
+        let mut warnings = Warnings::new(levels.iter().copied().collect());
+        let ast = parsing::parse_content_with(None, source, &mut warnings).unwrap();
+        let bound = binding::convert_with(&ast, &mut warnings).unwrap();
+        typing::convert_with(&bound, &mut warnings).unwrap();
+
+        warnings
+            .list()
+            .iter()
+            .map(|warning| (warning.lint, warning.error.message().to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn test_lints() {
+        let source = "ngop 'u a alu 1.\n\
+                      ngop lì'ukìng f fa b alu\n    \
+                          ngop 'u a alu 2 ulte\n    \
+                          wìntxu fa a\n\
+                      txew.\n\
+                      leyn kem si wìntxu fa 1 ftang.\n\
+                      f fa 3.\n";
+
+        assert_eq!(
+            lints(source, &[]),
+            vec![
+                (
+                    Lint::UnreachableCode,
+                    "Unreachable statement after a loop that never ends".to_owned()
+                ),
+                (
+                    Lint::ShadowedName,
+                    "'a' shadows a previous declaration".to_owned()
+                ),
+                (Lint::UnusedVariable, "Unused variable: 'a'".to_owned()),
+                (Lint::UnusedVariable, "Unused parameter: 'b'".to_owned()),
+            ]
+        );
+        assert_eq!(
+            lints(source, &[(Lint::UnusedVariable, Level::Allow)]).len(),
+            2
+        );
+
+        let suppressed = source
+            .replace(
+                "ngop 'u a alu 1.",
+                "ngop 'u a alu 1. ''allow: unused_variable''",
+            )
+            .replace(
+                "    ngop 'u a alu 2",
+                "    ''allow: shadowed_name\n    ngop 'u a alu 2",
+            )
+            .replace("f fa 3.", "f fa 3. ''allow: unreachable_code''");
+        assert_eq!(
+            lints(&suppressed, &[]),
+            vec![(Lint::UnusedVariable, "Unused parameter: 'b'".to_owned())]
+        );
+
+        // Only the last statement of a block gives it its value
+        let dropped = "ngop lì'ukìng f fa a alu a ulte may' a sì 1 livu teng txew.\n\
+                       3.\n\
+                       f fa 2.\n";
+        assert_eq!(
+            lints(dropped, &[])
+                .into_iter()
+                .map(|(lint, _)| lint)
+                .collect::<Vec<_>>(),
+            [Lint::UnusedValue, Lint::UnusedValue]
+        );
+    }
+
+    #[test]
+    fn test_imported_files() {
+        let mut warnings = Warnings::default();
+        let files = parsing::parse_files_with(
+            SourceCode::File("examples/features/lints/main.ay".to_owned()),
+            &mut warnings,
+        )
+        .unwrap();
+        binding::convert_files_with(&files, &mut warnings).unwrap();
+
+        // The module allows unused variables on its second line, not on the importer's
+        assert_eq!(
+            warnings
+                .list()
+                .iter()
+                .map(|warning| (warning.file.as_deref().unwrap(), warning.error.message()))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "examples/features/lints/lib.ay",
+                    "'yy' shadows a previous declaration"
+                ),
+                ("examples/features/lints/main.ay", "Unused variable: 'x'"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::{
+    fmt::Display,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use clap::ValueEnum;
+
+/// Language compiler messages are written in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+#[repr(u8)]
+pub enum Locale {
+    #[default]
+    #[value(name = "en")]
+    English,
+    #[value(name = "navi", alias = "na'vi")]
+    Navi,
+}
+
+/// Environment variable choosing the locale when the command line does not
+pub const LOCALE_VAR: &str = "AYSINVI_LANG";
+
+const UNSET: u8 = u8::MAX;
+static CURRENT: AtomicU8 = AtomicU8::new(UNSET);
+
+/// Chooses the locale of every message built from now on.
+pub fn set(locale: Locale) {
+    CURRENT.store(locale as u8, Ordering::Relaxed);
+}
+
+/// Locale set by [`set`], otherwise the one of [`LOCALE_VAR`], otherwise English
+pub fn current() -> Locale {
+    match CURRENT.load(Ordering::Relaxed) {
+        UNSET => std::env::var(LOCALE_VAR)
+            .ok()
+            .and_then(|lang| Locale::from_str(lang.trim(), true).ok())
+            .unwrap_or_default(),
+        id => Locale::value_variants()[id as usize],
+    }
+}
+
+impl Locale {
+    fn catalogue(self) -> &'static str {
+        match self {
+            Locale::English => include_str!("locales/en.txt"),
+            Locale::Navi => include_str!("locales/navi.txt"),
+        }
+    }
+
+    /// Every key of the catalogue along with its template
+    fn entries(self) -> impl Iterator<Item = (&'static str, &'static str)> {
+        self.catalogue()
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| line.split_once(" = "))
+    }
+
+    fn template(self, key: &str) -> Option<&'static str> {
+        self.entries()
+            .find(|(other, _)| *other == key)
+            .map(|(_, template)| template)
+    }
+}
+
+/// Message of `key` in `locale`, falling back to English and then to the key itself.
+pub fn message_in(locale: Locale, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let template = locale
+        .template(key)
+        .or_else(|| Locale::English.template(key))
+        .unwrap_or(key);
+
+    args.iter().fold(template.to_owned(), |res, (name, value)| {
+        res.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+/// Message of `$key`, its placeholders being filled with the variables of the same name, in the
+/// current locale unless one is given with `in`.
+macro_rules! message {
+    (in $locale:expr, $key:expr $(, $name:ident)* $(,)?) => {
+        $crate::error::locale::message_in(
+            $locale,
+            $key,
+            &[$((stringify!($name), &$name as &dyn std::fmt::Display)),*],
+        )
+    };
+
+    ($key:expr $(, $name:ident)* $(,)?) => {
+        $crate::error::locale::message!(in $crate::error::locale::current(), $key $(, $name)*)
+    };
+}
+
+pub(crate) use message;
+
+/// Joins items as in `a, b or c`, with the `or` of `locale`.
+pub fn either(locale: Locale, items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!(
+            "{} {} {last}",
+            rest.join(", "),
+            message_in(locale, "or", &[])
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::code::Code;
+
+    /// Names between braces in a template
+    fn placeholders(template: &str) -> Vec<&str> {
+        let mut res = template
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect::<Vec<_>>();
+        res.sort_unstable();
+        res
+    }
+
+    #[test]
+    fn test_catalogues() {
+        for locale in Locale::value_variants() {
+            for (key, template) in Locale::English.entries() {
+                let translated = locale.template(key);
+                assert!(translated.is_some(), "{key} missing in {locale:?}");
+                assert_eq!(
+                    placeholders(translated.unwrap()),
+                    placeholders(template),
+                    "{key} in {locale:?}"
+                );
+            }
+
+            for code in Code::ALL {
+                assert!(
+                    locale
+                        .entries()
+                        .any(|(key, _)| key.split('.').next() == Some(code.as_str())),
+                    "{code} missing in {locale:?}"
+                );
+            }
+        }
+
+        let name = "tìyarom";
+        assert_eq!(
+            message!(in Locale::Navi, "AY0102", name),
+            "Ke lu lì'ukìng 'tìyarom'"
+        );
+        assert_eq!(
+            either(
+                Locale::English,
+                &["a".to_owned(), "b".to_owned(), "c".to_owned()]
+            ),
+            "a, b or c"
+        );
+    }
+}
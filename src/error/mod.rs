@@ -1,3 +1,8 @@
+pub mod code;
+pub mod kind;
+pub mod lint;
+pub mod locale;
+pub mod report;
 pub mod span;
 pub mod trace;
 pub mod trace_error;
@@ -0,0 +1,363 @@
+use super::{
+    code::Code,
+    lint::{Level, Warning},
+    trace::{Stage, Trace, TraceError},
+};
+
+use clap::ValueEnum;
+use pest::error::LineColLocation;
+use serde_json::{json, Value};
+
+/// How errors are printed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum MessageFormat {
+    /// Colored code snippets, for people
+    #[default]
+    Human,
+    /// One JSON object per error or warning on its own line, told apart by their `severity`
+    Json,
+    /// A single SARIF 2.1.0 log of every error, printed once done
+    Sarif,
+}
+
+/// Prints the errors of a whole command in one format.
+///
+/// SARIF logs are single documents, so their errors are only printed by [`Reporter::finish`].
+#[derive(Debug)]
+pub struct Reporter {
+    format: MessageFormat,
+    results: Vec<Value>,
+    /// Descriptions of the error codes and lints of the results, by id
+    rules: Vec<(String, Value)>,
+}
+
+impl Reporter {
+    pub fn new(format: MessageFormat) -> Self {
+        Reporter {
+            format,
+            results: vec![],
+            rules: vec![],
+        }
+    }
+
+    /// Prints `trace` and the independent ones found along with it, their errors being in
+    /// `file` unless the traces know better.
+    pub fn report(&mut self, trace: &Trace, file: Option<&str>) {
+        if self.format == MessageFormat::Human {
+            eprintln!("{trace}");
+            return;
+        }
+
+        for trace in trace.traces() {
+            let file = trace.file().or(file);
+
+            match self.format {
+                MessageFormat::Json => eprintln!("{}", json(trace, file)),
+                _ => {
+                    let mut errors = trace.errors();
+                    let Some((stage, err)) = errors.next() else {
+                        continue;
+                    };
+                    let parents = errors.collect::<Vec<_>>();
+
+                    let mut result = self.sarif_result(stage, err, &parents, file);
+                    if let Some(code) = err.code() {
+                        let index = self.rule(code.as_str(), || {
+                            json!({
+                                "id": code.as_str(),
+                                "name": format!("{code:?}"),
+                                "shortDescription": { "text": code.title() },
+                                "help": { "text": code.explanation(), "markdown": code.explanation() },
+                            })
+                        });
+                        result["ruleId"] = json!(code.as_str());
+                        result["ruleIndex"] = json!(index);
+                    }
+
+                    self.results.push(result);
+                }
+            }
+        }
+    }
+
+    /// Prints `warning`, found in `file` unless it knows better.
+    pub fn warn(&mut self, warning: &Warning, file: Option<&str>) {
+        let file = warning.file.as_deref().or(file);
+
+        match self.format {
+            MessageFormat::Human => eprintln!("{warning}"),
+            MessageFormat::Json => eprintln!("{}", json_warning(warning, file)),
+            MessageFormat::Sarif => {
+                let mut result = self.sarif_result(&warning.stage, &warning.error, &[], file);
+                if warning.level != Level::Deny {
+                    result["level"] = json!("warning");
+                }
+
+                let description = warning
+                    .lint
+                    .to_possible_value()
+                    .and_then(|value| value.get_help().map(ToString::to_string))
+                    .unwrap_or_default();
+                let index = self.rule(warning.lint.name(), || {
+                    json!({
+                        "id": warning.lint.name(),
+                        "shortDescription": { "text": description },
+                        "defaultConfiguration": { "level": "warning" },
+                    })
+                });
+                result["ruleId"] = json!(warning.lint.name());
+                result["ruleIndex"] = json!(index);
+
+                self.results.push(result);
+            }
+        }
+    }
+
+    pub fn finish(self) {
+        if self.format == MessageFormat::Sarif {
+            eprintln!("{}", self.sarif_log());
+        }
+    }
+
+    /// Index of the rule `id`, described by `describe` when it is first met.
+    fn rule(&mut self, id: &str, describe: impl FnOnce() -> Value) -> usize {
+        self.rules
+            .iter()
+            .position(|(rule, _)| rule == id)
+            .unwrap_or_else(|| {
+                self.rules.push((id.to_owned(), describe()));
+                self.rules.len() - 1
+            })
+    }
+
+    /// Result of `err`, the errors it caused being related locations.
+    fn sarif_result(
+        &self,
+        stage: &Stage,
+        err: &dyn TraceError,
+        parents: &[(&Stage, &dyn TraceError)],
+        file: Option<&str>,
+    ) -> Value {
+        let mut related = err
+            .labels()
+            .iter()
+            .map(|label| sarif_location(file, label.span.line_col(), &label.message))
+            .collect::<Vec<_>>();
+        related.extend(parents.iter().map(|(stage, err)| {
+            sarif_location(
+                file,
+                &err.line_col(),
+                &format!("{stage:?}: {}", err.message()),
+            )
+        }));
+        related
+            .iter_mut()
+            .enumerate()
+            .for_each(|(id, location)| location["id"] = json!(id));
+
+        json!({
+            "level": "error",
+            "message": { "text": err.message() },
+            "locations": [sarif_location(file, &err.line_col(), "")],
+            "relatedLocations": related,
+            "fixes": err.suggestions().iter().map(|suggestion| {
+                let change = json!({
+                    "deletedRegion": sarif_region(suggestion.span.line_col()),
+                    "insertedContent": { "text": suggestion.replacement },
+                });
+                json!({
+                    "description": {
+                        "text": format!(
+                            "replace `{}` with `{}`",
+                            suggestion.span.as_str(),
+                            suggestion.replacement
+                        ),
+                    },
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": file.unwrap_or_default() },
+                        "replacements": [change],
+                    }],
+                })
+            }).collect::<Vec<_>>(),
+            "properties": {
+                "stage": format!("{stage:?}"),
+                "notes": err.notes(),
+                "help": err.help(),
+            },
+        })
+    }
+
+    fn sarif_log(&self) -> Value {
+        let rules = self.rules.iter().map(|(_, rule)| rule).collect::<Vec<_>>();
+
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "aysìnvi",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "columnKind": "unicodeCodePoints",
+                "results": self.results,
+            }],
+        })
+    }
+}
+
+/// Deepest error of the stack of `trace`, the errors it caused being related to it, without the
+/// independent traces.
+pub fn json(trace: &Trace, file: Option<&str>) -> Value {
+    let mut errors = trace.errors();
+    let Some(deepest) = errors.next() else {
+        return Value::Null;
+    };
+
+    diagnostic("error", deepest, &errors.collect::<Vec<_>>(), file)
+}
+
+/// `warning`, an error if its lint is denied, along with the name of the lint.
+pub fn json_warning(warning: &Warning, file: Option<&str>) -> Value {
+    let severity = match warning.level {
+        Level::Deny => "error",
+        _ => "warning",
+    };
+
+    let mut res = diagnostic(severity, (&warning.stage, &warning.error), &[], file);
+    res["lint"] = json!(warning.lint.name());
+    res
+}
+
+/// Error or warning, as printed in the JSON format whatever its `severity`.
+fn diagnostic(
+    severity: &str,
+    (stage, err): (&Stage, &dyn TraceError),
+    parents: &[(&Stage, &dyn TraceError)],
+    file: Option<&str>,
+) -> Value {
+    let mut res = entry(stage, err, file);
+    res["severity"] = json!(severity);
+    res["related"] = parents
+        .iter()
+        .map(|(stage, err)| entry(stage, *err, file))
+        .collect();
+    res
+}
+
+fn entry(stage: &Stage, err: &dyn TraceError, file: Option<&str>) -> Value {
+    json!({
+                "stage": format!("{stage:?}"),
+                "code": err.code().map(Code::as_str),
+                "file": file,
+                "range": range(&err.line_col()),
+                "message": err.message(),
+                "label": err.label(),
+                "labels": err.labels().iter().map(|label| json!({
+                    "range": range(label.span.line_col()),
+                    "message": label.message,
+                })).collect::<Vec<_>>(),
+                "notes": err.notes(),
+                "help": err.help(),
+                "suggestions": err.suggestions().iter().map(|suggestion| json!({
+                    "range": range(suggestion.span.line_col()),
+                    "replacement": suggestion.replacement,
+                })).collect::<Vec<_>>(),
+    })
+}
+
+/// Start and end, columns counting characters from 1 and the end being excluded
+fn bounds(location: &LineColLocation) -> ((usize, usize), (usize, usize)) {
+    match *location {
+        LineColLocation::Pos(pos) => (pos, pos),
+        LineColLocation::Span(start, end) => (start, end),
+    }
+}
+
+fn range(location: &LineColLocation) -> Value {
+    let ((start_line, start_column), (end_line, end_column)) = bounds(location);
+    json!({
+        "start": { "line": start_line, "column": start_column },
+        "end": { "line": end_line, "column": end_column },
+    })
+}
+
+fn sarif_region(location: &LineColLocation) -> Value {
+    let ((start_line, start_column), (end_line, end_column)) = bounds(location);
+    json!({
+        "startLine": start_line,
+        "startColumn": start_column,
+        "endLine": end_line,
+        "endColumn": end_column,
+    })
+}
+
+fn sarif_location(file: Option<&str>, location: &LineColLocation, message: &str) -> Value {
+    let mut physical = json!({ "region": sarif_region(location) });
+    if let Some(file) = file {
+        physical["artifactLocation"] = json!({ "uri": file });
+    }
+
+    let mut res = json!({ "physicalLocation": physical });
+    if !message.is_empty() {
+        res["message"] = json!({ "text": message });
+    }
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{binding, parsing},
+        error::lint::Warnings,
+    };
+
+    #[test]
+    fn test_message_formats() {
+        let ast = parsing::parse_content(None, "ngop lì'ukìng t.aron fa a alu a.\ntìyarom fa 1.")
+            .unwrap();
+        let trace = binding::convert(&ast).unwrap_err();
+
+        assert_eq!(
+            json(&trace, Some("test.ay")).to_string(),
+            "{\"stage\":\"Binding\",\"code\":\"AY0102\",\"file\":\"test.ay\",\
+             \"range\":{\"start\":{\"line\":2,\"column\":1},\"end\":{\"line\":2,\"column\":8}},\
+             \"message\":\"Undefined function: 'tìyarom'\",\
+             \"label\":\"no function named 'tìyarom' in scope\",\
+             \"labels\":[{\"range\":{\"start\":{\"line\":1,\"column\":15},\
+             \"end\":{\"line\":1,\"column\":21}},\"message\":\"a similar function is declared here\"}],\
+             \"notes\":[],\"help\":[],\
+             \"suggestions\":[{\"range\":{\"start\":{\"line\":2,\"column\":1},\
+             \"end\":{\"line\":2,\"column\":8}},\"replacement\":\"tìyaron\"}],\
+             \"severity\":\"error\",\"related\":[]}"
+        );
+
+        let mut warnings = Warnings::default();
+        let unused = parsing::parse_content(None, "ngop 'u x alu 1.").unwrap();
+        binding::convert_with(&unused, &mut warnings).unwrap();
+        let warning = json_warning(&warnings.list()[0], Some("test.ay"));
+        assert_eq!(warning["severity"], "warning");
+        assert_eq!(warning["lint"], "unused_variable");
+        assert_eq!(warning["message"], "Unused variable: 'x'");
+        assert_eq!(warning["related"], json!([]));
+
+        let mut reporter = Reporter::new(MessageFormat::Sarif);
+        reporter.report(&trace, Some("test.ay"));
+        let log = reporter.sarif_log();
+        let result = &log["runs"][0]["results"][0];
+
+        assert_eq!(log["runs"][0]["tool"]["driver"]["rules"][0]["id"], "AY0102");
+        assert_eq!(result["ruleId"], "AY0102");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "test.ay"
+        );
+        assert_eq!(
+            result["fixes"][0]["artifactChanges"][0]["replacements"][0]["insertedContent"]["text"],
+            "tìyaron"
+        );
+    }
+}
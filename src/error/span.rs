@@ -1,12 +1,29 @@
 use pest::error::LineColLocation;
 
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+use std::ops::Range;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Span {
     line: String,
     location: LineColLocation,
+    range: Range<usize>,
+    /// Whole lines the span is on
+    context: String,
 }
 
 impl Span {
+    /// Empty span at the start of a file, for errors about the file as a whole
+    pub fn file_start() -> Self {
+        Self {
+            line: String::new(),
+            location: LineColLocation::Pos((1, 1)),
+            range: 0..0,
+            context: String::new(),
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         self.line.as_ref()
     }
@@ -14,6 +31,57 @@ impl Span {
     pub fn line_col(&self) -> &LineColLocation {
         &self.location
     }
+
+    /// Whole lines the span is on, starting at its first line
+    pub fn context(&self) -> &str {
+        self.context.as_ref()
+    }
+
+    /// Byte offsets of the span in the source it was parsed from
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Narrows the span down to the first whole-word occurrence of `word`, e.g. the name in a
+    /// declaration.
+    pub fn find_word(&self, word: &str) -> Option<Span> {
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '\'';
+
+        let index = self.line.match_indices(word).find_map(|(index, _)| {
+            let before = self.line[..index].chars().next_back();
+            let after = self.line[index + word.len()..].chars().next();
+
+            (!before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)).then_some(index)
+        })?;
+
+        let (line, col) = match self.location {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+
+        // Moves a line/column position over some text
+        let advance = |(line, col): (usize, usize), text: &str| match text.rsplit_once('\n') {
+            Some((before, after)) => (
+                line + before.matches('\n').count() + 1,
+                after.chars().count() + 1,
+            ),
+            None => (line, col + text.chars().count()),
+        };
+
+        let start = advance((line, col), &self.line[..index]);
+        let end = advance(start, word);
+
+        Some(Self {
+            line: word.to_owned(),
+            location: LineColLocation::Span(start, end),
+            range: self.range.start + index..self.range.start + index + word.len(),
+            context: self
+                .context
+                .split_inclusive('\n')
+                .skip(start.0 - line)
+                .take(end.0 - start.0 + 1)
+                .collect(),
+        })
+    }
 }
 
 impl From<pest::Span<'_>> for Span {
@@ -21,6 +89,24 @@ impl From<pest::Span<'_>> for Span {
         Self {
             line: span.as_str().to_string(),
             location: LineColLocation::Span(span.start_pos().line_col(), span.end_pos().line_col()),
+            range: span.start()..span.end(),
+            context: span.lines().collect(),
         }
     }
 }
+
+impl Serialize for Span {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (start, end) = match self.location {
+            LineColLocation::Pos(pos) => (pos, pos),
+            LineColLocation::Span(start, end) => (start, end),
+        };
+
+        let mut state = serializer.serialize_struct("Span", 4)?;
+        state.serialize_field("start", &start)?;
+        state.serialize_field("end", &end)?;
+        state.serialize_field("range", &(self.range.start, self.range.end))?;
+        state.serialize_field("text", &self.line)?;
+        state.end()
+    }
+}
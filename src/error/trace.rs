@@ -1,13 +1,22 @@
-use super::{span::Span, trace_error::Error};
-
-use crate::{highlight::highlight_aysinvi, parsing::*};
+use super::{
+    code::Code,
+    kind::ErrorKind,
+    locale::message,
+    span::Span,
+    trace_error::{Error, Label, Suggestion},
+};
 
-use pest::{
-    error::{Error as PestError, ErrorVariant, LineColLocation},
-    iterators::{Pair, Pairs},
+use crate::{
+    ast::parsing::*,
+    highlight::{
+        render::{self, Role},
+        Renderer,
+    },
 };
 
-use std::fmt::Debug;
+use pest::{error::LineColLocation, iterators::Pair};
+
+use std::{collections::BTreeMap, fmt::Debug, ops::Range};
 
 #[derive(Debug)]
 pub enum Stage {
@@ -16,36 +25,67 @@ pub enum Stage {
     AstBuilding,
     Binding,
     Typing,
+    Formatting,
+    Rewriting,
     Compiling,
+    Running,
 }
 
 pub trait TraceError: Debug {
     fn from_span(span: Span, message: &str) -> Self
     where
         Self: Sized;
+    /// What went wrong, `None` for errors only giving context to deeper ones
+    fn kind(&self) -> Option<&ErrorKind> {
+        None
+    }
+    /// Stable code identifying the kind of error, see `aysinvi explain`
+    fn code(&self) -> Option<Code> {
+        self.kind().map(ErrorKind::code)
+    }
     fn line_col(&self) -> LineColLocation;
     fn line(&self) -> &str;
+    /// Whole lines the error is on, starting at its first line
+    fn context(&self) -> &str {
+        self.line()
+    }
     fn message(&self) -> &str;
+    /// Text underlining the code the error points at
+    fn label(&self) -> Option<&str> {
+        None
+    }
+    fn labels(&self) -> &[Label] {
+        &[]
+    }
+    fn notes(&self) -> &[String] {
+        &[]
+    }
+    fn help(&self) -> &[String] {
+        &[]
+    }
+    fn suggestions(&self) -> &[Suggestion] {
+        &[]
+    }
 }
 
 #[derive(Debug, Default)]
 pub struct Trace {
     stack: Vec<(Stage, Box<dyn TraceError>)>,
+    /// File the errors are in, if known
+    file: Option<String>,
+    /// Errors unrelated to this one, found by a stage that kept going after it
+    others: Vec<Trace>,
 }
 
 impl<T: TraceError + 'static> From<(Stage, T)> for Trace {
     fn from((stage, err): (Stage, T)) -> Self {
-        Trace {
-            stack: vec![(stage, Box::new(err))],
-        }
+        Trace::new(stage, err)
     }
 }
 
 impl<T: TraceError + 'static> From<T> for Trace {
     fn from(err: T) -> Self {
-        Trace {
-            stack: vec![(Stage::Unknown, Box::new(err))],
-        }
+        Trace::new(Stage::Unknown, err)
     }
 }
 
@@ -53,19 +93,63 @@ impl Trace {
     pub fn new<T: TraceError + 'static>(stage: Stage, err: T) -> Self {
         Trace {
             stack: vec![(stage, Box::new(err))],
+            file: None,
+            others: vec![],
         }
     }
 
+    /// Gathers independent traces into the first one, `None` if there is none.
+    pub fn collect(traces: impl IntoIterator<Item = Trace>) -> Option<Self> {
+        let mut traces = traces.into_iter().flat_map(|mut trace| {
+            let others = std::mem::take(&mut trace.others);
+            std::iter::once(trace).chain(others)
+        });
+
+        let mut first = traces.next()?;
+        first.others = traces.collect();
+        Some(first)
+    }
+
+    /// This trace followed by the independent ones found along with it
+    pub fn traces(&self) -> impl Iterator<Item = &Trace> {
+        std::iter::once(self).chain(self.others.iter())
+    }
+
+    /// Records the file the errors are in, unless a deeper stage already did.
+    pub fn in_file(mut self, file: &str) -> Self {
+        self.file.get_or_insert_with(|| file.to_owned());
+        self.others = self
+            .others
+            .into_iter()
+            .map(|other| other.in_file(file))
+            .collect();
+        self
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
     pub fn new_from_pair(pair: &Pair<Rule>, message: String) -> Self {
         let mut res = Trace::default();
         res.push_pest_error(Stage::Parsing, pair, message);
         res
     }
 
+    /// Kind of the deepest error, the one that caused the others
+    pub fn kind(&self) -> Option<&ErrorKind> {
+        self.errors().next().and_then(|(_, err)| err.kind())
+    }
+
     pub fn push<T: TraceError + 'static>(&mut self, stage: Stage, err: T) {
         self.stack.push((stage, Box::new(err)))
     }
 
+    /// Every error of the stack, deepest first
+    pub fn errors(&self) -> impl Iterator<Item = (&Stage, &dyn TraceError)> {
+        self.stack.iter().map(|(stage, err)| (stage, err.as_ref()))
+    }
+
     pub fn push_pest_error(&mut self, stage: Stage, pair: &Pair<Rule>, message: String) {
         self.stack.push((
             stage,
@@ -76,65 +160,282 @@ impl Trace {
 
 impl std::fmt::Display for Trace {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "Deepest error first\n{}",
+        write!(f, "{}", self.render(render::current()))
+    }
+}
+
+impl Trace {
+    /// Every error of this trace and the independent ones, styled by `renderer`.
+    pub fn render(&self, renderer: &Renderer) -> String {
+        std::iter::once(self)
+            .chain(&self.others)
+            .map(|trace| trace.render_one(renderer))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Renders the stack of this trace only, without the independent ones.
+    fn render_one(&self, renderer: &Renderer) -> String {
+        let explain = match self.kind().map(ErrorKind::code) {
+            Some(code) => format!("\n{}\n", message!("trace.explain", code)),
+            None => String::new(),
+        };
+
+        format!(
+            "{}\n{}{explain}",
+            message!("trace.deepest"),
             self.stack
                 .iter()
-                .map(|(stage, err)| {
-                    let line_nbr = match err.line_col() {
-                        LineColLocation::Pos((y, _)) => y,
-                        LineColLocation::Span((ys, _), _) => ys,
-                    };
-
-                    let line_nbr_len = line_nbr.to_string().len();
-
-                    let padding = " ".repeat(line_nbr_len);
-
-                    let arrow = format!("{}>", "-".repeat(line_nbr_len));
-
-                    let coords = match err.line_col() {
-                        LineColLocation::Pos((y, x)) => format!("{y}:{x}"),
-                        LineColLocation::Span((ys, xs), (ye, xe)) => {
-                            format!("{ys}:{xs} -> {ye}:{xe}")
-                        }
-                    };
-
-                    let pipe = "\x1b[2;37m|\x1b[0m";
-
-                    // ---> STAGE | COORDS
-                    //    |
-                    // NBR| LINE
-                    //    |
-                    //    = ERROR
-                    format!(
-                        "{arrow} \x1b[33m{stage:?}\x1b[0m {pipe} \x1b[34m{coords}\x1b[0m\n\
-                         {padding}{pipe}\n\
-                         {}\n\
-                         {padding}{pipe}\n\
-                         {padding}= {}\n",
-                        // Line number and line
-                        err.line()
-                            .split('\n')
-                            .enumerate()
-                            // line.trim().is_empty()
-                            .map(|(index, line)| if !line.trim().is_empty() {
-                                format!(
-                                    "\x1b[2;37m{}\x1b[0m{pipe} {}",
-                                    line_nbr + index,
-                                    highlight_aysinvi(line.trim_end())
-                                )
-                            } else {
-                                "".to_owned()
-                            })
-                            .filter(|line| !line.is_empty())
-                            .collect::<Vec<String>>()
-                            .join("\n"),
-                        // Error
-                        err.message()
-                    )
-                })
+                .map(|(stage, err)| render(renderer, stage, err.as_ref()))
                 .collect::<String>(),
         )
     }
 }
+
+impl std::error::Error for Trace {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.kind().map(|kind| kind as _)
+    }
+}
+
+/// Part of a line to underline, with `^` under the error itself and `-` under its labels
+struct Underline<'a> {
+    line: usize,
+    /// Columns, starting at 1, end excluded
+    columns: Range<usize>,
+    primary: bool,
+    message: Option<&'a str>,
+}
+
+// ---> STAGE [CODE] | COORDS
+//    |
+// NBR| LINE
+//    |     ^^^^ LABEL
+//    |
+//    = ERROR
+//    = note: NOTE
+//    = help: HELP
+pub(crate) fn render(renderer: &Renderer, stage: &Stage, err: &dyn TraceError) -> String {
+    let mut lines = BTreeMap::<usize, &str>::new();
+    let mut underlines = vec![];
+
+    let line_col = err.line_col();
+    underline(
+        &mut lines,
+        &mut underlines,
+        &line_col,
+        err.context(),
+        true,
+        err.label(),
+    );
+    for label in err.labels() {
+        underline(
+            &mut lines,
+            &mut underlines,
+            label.span.line_col(),
+            label.span.context(),
+            false,
+            Some(label.message.as_ref()),
+        );
+    }
+
+    let line_nbr = match line_col {
+        LineColLocation::Pos((y, _)) => y,
+        LineColLocation::Span((ys, _), _) => ys,
+    };
+    let width = lines.keys().max().unwrap_or(&line_nbr).to_string().len();
+    let padding = " ".repeat(width);
+    let arrow = format!("{}>", "-".repeat(width));
+    let pipe = renderer.paint(Role::Gutter, "|");
+
+    let coords = match line_col {
+        LineColLocation::Pos((y, x)) => format!("{y}:{x}"),
+        LineColLocation::Span((ys, xs), (ye, xe)) => {
+            format!("{ys}:{xs} -> {ye}:{xe}")
+        }
+    };
+
+    let mut snippet = vec![];
+    let mut previous = None;
+    for (&nbr, line) in &lines {
+        let mut marks = underlines
+            .iter()
+            .filter(|underline| underline.line == nbr)
+            .collect::<Vec<_>>();
+        marks.sort_by_key(|underline| underline.columns.start);
+        if line.trim().is_empty() && marks.is_empty() {
+            continue;
+        }
+
+        if previous.is_some_and(|previous| previous + 1 < nbr) {
+            snippet.push(format!("{padding}{}", renderer.paint(Role::Gutter, "...")));
+        }
+        previous = Some(nbr);
+
+        snippet.push(format!(
+            "{}{pipe} {}",
+            renderer.paint(Role::Gutter, &format!("{nbr:>width$}")),
+            renderer.code(line.trim_end())
+        ));
+
+        for Underline {
+            columns,
+            primary,
+            message,
+            ..
+        } in marks
+        {
+            // Tabs are kept so that the marks line up with the code
+            let indent = line
+                .chars()
+                .take(columns.start - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let (mark, role) = if *primary {
+                ('^', Role::Error)
+            } else {
+                ('-', Role::Secondary)
+            };
+            let marks = format!(
+                "{}{}",
+                mark.to_string().repeat(columns.len()),
+                message
+                    .map(|message| format!(" {message}"))
+                    .unwrap_or_default()
+            );
+
+            snippet.push(format!(
+                "{padding}{pipe} {indent}{}",
+                renderer.paint(role, &marks)
+            ));
+        }
+    }
+
+    let note = renderer.paint(Role::Emphasis, "note:");
+    let help = renderer.paint(Role::Emphasis, "help:");
+    let footer = std::iter::once(err.message().to_owned())
+        .chain(err.notes().iter().map(|text| format!("{note} {text}")))
+        .chain(err.help().iter().map(|text| format!("{help} {text}")))
+        .chain(err.suggestions().iter().map(|suggestion| {
+            format!(
+                "{help} replace `{}` with `{}`",
+                suggestion.span.as_str(),
+                suggestion.replacement
+            )
+        }))
+        .map(|line| format!("{padding}= {line}\n"))
+        .collect::<String>();
+
+    let code = err
+        .code()
+        .map(|code| format!(" {}", renderer.paint(Role::Error, &format!("[{code}]"))))
+        .unwrap_or_default();
+
+    // Errors about a whole file, e.g. an unreadable one, have no code to show
+    let snippet = match snippet.is_empty() {
+        true => String::new(),
+        false => format!("{padding}{pipe}\n{}\n{padding}{pipe}\n", snippet.join("\n")),
+    };
+
+    format!(
+        "{arrow} {}{code} {pipe} {}\n{snippet}{footer}",
+        renderer.paint(Role::Stage, &format!("{stage:?}")),
+        renderer.paint(Role::Location, &coords),
+    )
+}
+
+/// Records the lines `location` is on and how to underline them.
+fn underline<'a>(
+    lines: &mut BTreeMap<usize, &'a str>,
+    underlines: &mut Vec<Underline<'a>>,
+    location: &LineColLocation,
+    context: &'a str,
+    primary: bool,
+    message: Option<&'a str>,
+) {
+    let (start, end) = match *location {
+        LineColLocation::Pos(pos) => (pos, pos),
+        LineColLocation::Span(start, end) => (start, end),
+    };
+    context.lines().enumerate().for_each(|(index, line)| {
+        lines.insert(start.0 + index, line);
+    });
+
+    // Spans ending with a line break end on the line before
+    let end = match end {
+        (line, 1) if line > start.0 => {
+            let text = lines.get(&(line - 1)).copied().unwrap_or_default();
+            (line - 1, text.trim_end().chars().count() + 1)
+        }
+        end => end,
+    };
+
+    for line in start.0..=end.0 {
+        let text = lines.get(&line).copied().unwrap_or_default();
+        let first = if line == start.0 {
+            start.1
+        } else {
+            text.chars().take_while(|c| c.is_whitespace()).count() + 1
+        };
+        let last = if line == end.0 {
+            end.1
+        } else {
+            text.trim_end().chars().count() + 1
+        };
+
+        underlines.push(Underline {
+            line,
+            columns: first..last.max(first + 1),
+            primary,
+            message: (line == end.0).then_some(message).flatten(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Renderer;
+    use crate::ast::{binding, lib::SourceCode, parsing};
+
+    #[test]
+    fn test_labels() {
+        let source = "ngop lì'ukìng t.aron fa a alu\n    a\ntxew.\nngop 'u b alu tìyarom fa 1.\n";
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let trace = binding::convert(&ast).unwrap_err();
+
+        assert_eq!(
+            trace.render(&Renderer::Plain),
+            "Deepest error first\n\
+             -> Binding [AY0102] | 4:15 -> 4:22\n \
+             |\n\
+             1| ngop lì'ukìng t.aron fa a alu\n \
+             |               ------ a similar function is declared here\n \
+             ...\n\
+             4| ngop 'u b alu tìyarom fa 1.\n \
+             |               ^^^^^^^ no function named 'tìyarom' in scope\n \
+             |\n \
+             = Undefined function: 'tìyarom'\n \
+             = help: replace `tìyarom` with `tìyaron`\n\n\
+             For more information about this error, try `aysinvi explain AY0102`.\n"
+        );
+    }
+
+    #[test]
+    fn test_frames() {
+        let source = "txo 1, tsakrr wìntxu fa ke ke 7777777777777777777777777 txew.\n";
+        let trace = parsing::parse(SourceCode::Content(source.to_owned())).unwrap_err();
+
+        assert_eq!(
+            trace
+                .errors()
+                .map(|(_, err)| err.message().to_owned())
+                .collect::<Vec<_>>(),
+            [
+                "`7777777777777777777777777` is too large to be a number",
+                "in a negation (`ke`)",
+                "in a function call",
+                "in a condition (`txo`)",
+            ]
+        );
+    }
+}
@@ -1,14 +1,96 @@
-use super::{span::Span, trace::TraceError};
+use super::{
+    kind::{ErrorKind, ParsingError},
+    span::Span,
+    trace::TraceError,
+};
 
-use crate::parsing::Rule;
+use crate::ast::parsing::Rule;
 
-use pest::error::LineColLocation;
+use pest::error::{ErrorVariant, LineColLocation};
 
 #[derive(Debug)]
 pub struct Error {
+    kind: Option<ErrorKind>,
     line_col: LineColLocation,
     line: String,
+    context: String,
     message: String,
+    label: Option<String>,
+    labels: Vec<Label>,
+    notes: Vec<String>,
+    help: Vec<String>,
+    suggestions: Vec<Suggestion>,
+}
+
+/// Code related to an error without being its cause, e.g. the declaration of a name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// Replacement of the code at `span` that would fix an error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Suggestion {
+    /// Replaces the span in `source`, `None` if it was not parsed from `source`.
+    pub fn apply(&self, source: &str) -> Option<String> {
+        let range = self.span.range();
+        (source.get(range.clone())? == self.span.as_str()).then(|| {
+            format!(
+                "{}{}{}",
+                &source[..range.start],
+                self.replacement,
+                &source[range.end..]
+            )
+        })
+    }
+}
+
+impl Error {
+    /// Error at `span` described by `kind`.
+    pub fn new(span: Span, kind: impl Into<ErrorKind>) -> Self {
+        let kind = kind.into();
+        Self::from_span(span, &kind.to_string()).with_kind(kind)
+    }
+
+    pub fn with_kind(mut self, kind: impl Into<ErrorKind>) -> Self {
+        self.kind = Some(kind.into());
+        self
+    }
+
+    /// Explains what is wrong with the code the error points at, under it.
+    pub fn with_label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_owned());
+        self
+    }
+
+    pub fn with_secondary_label(mut self, span: Span, message: &str) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.to_owned(),
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_owned());
+        self
+    }
+
+    pub fn with_help(mut self, help: &str) -> Self {
+        self.help.push(help.to_owned());
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Suggestion) -> Self {
+        self.suggestions.push(suggestion);
+        self
+    }
 }
 
 impl TraceError for Error {
@@ -17,12 +99,23 @@ impl TraceError for Error {
         Self: Sized,
     {
         Self {
+            kind: None,
             line_col: span.line_col().clone(),
             line: span.as_str().to_owned(),
+            context: span.context().to_owned(),
             message: message.to_owned(),
+            label: None,
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+            suggestions: vec![],
         }
     }
 
+    fn kind(&self) -> Option<&ErrorKind> {
+        self.kind.as_ref()
+    }
+
     fn line_col(&self) -> LineColLocation {
         self.line_col.clone()
     }
@@ -31,17 +124,54 @@ impl TraceError for Error {
         self.line.as_ref()
     }
 
+    fn context(&self) -> &str {
+        self.context.as_ref()
+    }
+
     fn message(&self) -> &str {
         self.message.as_ref()
     }
+
+    fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    fn help(&self) -> &[String] {
+        &self.help
+    }
+
+    fn suggestions(&self) -> &[Suggestion] {
+        &self.suggestions
+    }
 }
 
+/// Syntax error, its message being that of the kind unless pest was given a custom one
 impl From<pest::error::Error<Rule>> for Error {
     fn from(err: pest::error::Error<Rule>) -> Self {
+        let kind = ErrorKind::from(ParsingError::syntax(&err.variant));
+
         Self {
+            message: match &err.variant {
+                ErrorVariant::CustomError { message } => message.clone(),
+                ErrorVariant::ParsingError { .. } => kind.to_string(),
+            },
+            kind: Some(kind),
             line_col: err.line_col.clone(),
             line: err.line().to_owned(),
-            message: err.variant.message().to_string(),
+            context: err.line().to_owned(),
+            label: None,
+            labels: vec![],
+            notes: vec![],
+            help: vec![],
+            suggestions: vec![],
         }
     }
 }
@@ -0,0 +1,71 @@
+use crate::{
+    ast::{binding, parsing},
+    error::trace::Trace,
+};
+
+/// Bound on the number of passes, in case fixes keep introducing new errors
+const MAX_FIXES: usize = 64;
+
+/// Result of applying every suggested fix to a file
+pub struct Fixes {
+    pub source: String,
+    pub applied: usize,
+    /// First error without a suggestion, if any
+    pub remaining: Option<Trace>,
+}
+
+/// Binds `source` as the file at `path`, applying the suggestion of each error until there is
+/// none left.
+pub fn fix_source(path: Option<&str>, source: &str) -> Fixes {
+    let mut fixes = Fixes {
+        source: source.to_owned(),
+        applied: 0,
+        remaining: None,
+    };
+
+    while fixes.applied < MAX_FIXES {
+        let Err(trace) = parsing::parse_content(path, fixes.source.as_ref())
+            .and_then(|ast| binding::convert(&ast))
+        else {
+            return fixes;
+        };
+
+        let fixed = trace
+            .traces()
+            .flat_map(Trace::errors)
+            .flat_map(|(_, err)| err.suggestions())
+            .find_map(|suggestion| suggestion.apply(fixes.source.as_ref()));
+
+        match fixed {
+            Some(fixed) => {
+                fixes.source = fixed;
+                fixes.applied += 1;
+            }
+            None => {
+                fixes.remaining = Some(trace);
+                return fixes;
+            }
+        }
+    }
+
+    fixes
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fix_conjugated_call() {
+        let source =
+            "ngop lì'ukìng t.aron fa a alu a.\nngop 'u bean alu tìyarom fa 1.\nwìntxu fa ben.\n";
+        let fixes = fix_source(None, source);
+
+        assert!(fixes.remaining.is_none());
+        assert_eq!(fixes.applied, 2);
+        assert_eq!(
+            fixes.source,
+            "ngop lì'ukìng t.aron fa a alu a.\nngop 'u bean alu tìyaron fa 1.\nwìntxu fa bean.\n"
+        );
+    }
+}
@@ -0,0 +1,397 @@
+use crate::{
+    ast::{
+        cst::{self, TokenKind},
+        lib::{AyNode, ComparisonOperator},
+        parsing::{self, Expr, ModUse, Statement},
+    },
+    error::{
+        kind::FormattingError,
+        span::Span,
+        trace::{Stage, Trace},
+        trace_error::Error,
+    },
+};
+
+use std::ops::Range;
+
+use serde_json::Value;
+
+const INDENT: &str = "    ";
+
+/// Pretty-printer turning a parsed AST back into canonical source code.
+///
+/// Comments are not part of the AST, so they are taken from the concrete syntax tree and written
+/// back before the statement that follows them, or after the one they end the line of.
+struct Formatter<'s> {
+    source: &'s str,
+    comments: Vec<Range<usize>>,
+    next_comment: usize,
+    last_end: usize,
+}
+
+/// Formats a single file, failing if the result would not parse back to the same AST.
+pub fn format_source(source: &str) -> Result<String, Trace> {
+    let cst = cst::parse(source)?;
+    let (uses, ast) = parsing::build_ast(&cst)?;
+
+    let mut formatter = Formatter {
+        source,
+        comments: cst
+            .tokens()
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| token.span.start()..token.span.end())
+            .collect(),
+        next_comment: 0,
+        last_end: 0,
+    };
+
+    let mut output = uses
+        .iter()
+        .map(|node| {
+            let comments = formatter.comments_before(&node.span, 0);
+            formatter.last_end = node.span.range().end;
+            let trailing = formatter.trailing_comment(&node.span);
+            format!("{comments}{}.{trailing}\n", mod_use(&node.inner))
+        })
+        .collect::<String>();
+
+    for node in ast.iter() {
+        output += &formatter.comments_before(&node.span, 0);
+        output += &formatter.statement(node, 0);
+        output += ".";
+        output += &formatter.trailing_comment(&node.span);
+        output += "\n";
+    }
+
+    output += &formatter.comments_before_offset(source.len(), 0);
+    output.truncate(output.trim_end().len());
+    if !output.is_empty() {
+        output.push('\n');
+    }
+
+    let whole_file: Span = pest::Span::new(source, 0, source.len())
+        .expect("The whole source is a valid span")
+        .into();
+
+    let (new_uses, new_ast) = parsing::parse_module(output.as_ref()).map_err(|mut trace| {
+        trace.push(
+            Stage::Formatting,
+            Error::new(whole_file.clone(), FormattingError::Unparsable),
+        );
+        trace
+    })?;
+
+    if without_spans(&(uses, ast)) != without_spans(&(new_uses, new_ast)) {
+        return Err(Trace::new(
+            Stage::Formatting,
+            Error::new(whole_file, FormattingError::MeaningChanged),
+        ));
+    }
+
+    Ok(output)
+}
+
+impl Formatter<'_> {
+    fn comments_before(&mut self, span: &Span, depth: usize) -> String {
+        let res = self.comments_before_offset(span.range().start, depth);
+        self.last_end = span.range().end;
+        res
+    }
+
+    /// Writes every comment starting before `offset`, each on its own line.
+    fn comments_before_offset(&mut self, offset: usize, depth: usize) -> String {
+        let mut res = String::new();
+
+        while let Some(comment) = self
+            .comments
+            .get(self.next_comment)
+            .filter(|comment| comment.start < offset)
+            .cloned()
+        {
+            res += &self.blank_line(comment.start);
+            res += &format!(
+                "{}{}\n",
+                INDENT.repeat(depth),
+                self.source[comment.clone()].trim_end()
+            );
+
+            self.last_end = comment.end;
+            self.next_comment += 1;
+        }
+
+        res + &self.blank_line(offset)
+    }
+
+    /// Comment following the code of `span` on the same line, which is kept there as suppression
+    /// comments apply to the line they end.
+    fn trailing_comment(&mut self, span: &Span) -> String {
+        let end = self.code_end(span);
+        let Some(comment) = self
+            .comments
+            .get(self.next_comment)
+            .filter(|comment| {
+                self.source.get(end..comment.start).is_some_and(|gap| {
+                    !gap.contains('\n')
+                        && gap
+                            .split_whitespace()
+                            .all(|word| [".", "ulte"].contains(&word))
+                })
+            })
+            .cloned()
+        else {
+            return String::new();
+        };
+
+        self.last_end = comment.end;
+        self.next_comment += 1;
+        format!(" {}", self.source[comment].trim_end())
+    }
+
+    /// End of the last token of `span` that is neither whitespace nor a comment, as the spans of
+    /// statements run until the next one.
+    fn code_end(&self, span: &Span) -> usize {
+        let range = span.range();
+        let mut end = range.end;
+
+        loop {
+            end = range.start + self.source[range.start..end].trim_end().len();
+            match self.comments.iter().find(|comment| {
+                comment.start >= range.start
+                    && comment.start + self.source[(*comment).clone()].trim_end().len() == end
+            }) {
+                Some(comment) => end = comment.start,
+                None => return end,
+            }
+        }
+    }
+
+    /// Keeps a single blank line where the source had at least one.
+    fn blank_line(&self, offset: usize) -> String {
+        let gap = self.source.get(self.last_end..offset).unwrap_or_default();
+
+        let lines = gap.split('\n').collect::<Vec<&str>>();
+        let inner_lines = lines
+            .get(1..lines.len().saturating_sub(1))
+            .unwrap_or_default();
+
+        if self.last_end != 0 && inner_lines.iter().any(|line| line.trim().is_empty()) {
+            "\n".to_owned()
+        } else {
+            String::new()
+        }
+    }
+
+    fn block(&mut self, body: &[AyNode<Statement>], depth: usize) -> String {
+        body.iter()
+            .enumerate()
+            .map(|(index, node)| {
+                format!(
+                    "{}{}{}{}{}",
+                    self.comments_before(&node.span, depth),
+                    INDENT.repeat(depth),
+                    self.statement(node, depth),
+                    if index + 1 < body.len() { " ulte" } else { "" },
+                    self.trailing_comment(&node.span)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn statement(&mut self, AyNode { inner, .. }: &AyNode<Statement>, depth: usize) -> String {
+        let indent = INDENT.repeat(depth);
+
+        match inner {
+            Statement::FunDec { name, args, body } => {
+                let mut res = format!("ngop lì'ukìng {name}");
+
+                if !args.is_empty() {
+                    res += &format!(" fa {}", args.join(" sì "));
+                }
+
+                if !body.is_empty() {
+                    res += &format!(" alu\n{}\n{indent}txew", self.block(body, depth + 1));
+                }
+
+                res
+            }
+            Statement::VarDec { names, values } => format!(
+                "ngop {} {} alu {}",
+                match names.len() {
+                    1 => "'u",
+                    2 => "meu",
+                    3 => "pxeu",
+                    _ => "ayu",
+                },
+                names.join(" sì "),
+                values
+                    .iter()
+                    .map(expr)
+                    .collect::<Vec<String>>()
+                    .join(" sì ")
+            ),
+            Statement::Expr(node) => expr(node),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let mut res = format!(
+                    "txo {}, tsakrr\n{}",
+                    expr(cond),
+                    self.block(then, depth + 1)
+                );
+
+                if otherwise.is_empty() {
+                    res += &format!("\n{indent}txew");
+                } else {
+                    res += &format!(",\n{indent}txokefyaw\n{}", self.block(otherwise, depth + 1));
+                }
+
+                res
+            }
+            Statement::Loop { cond, body } => format!(
+                "leyn{} kem si\n{}\n{indent}ftang",
+                cond.as_ref()
+                    .map(|cond| format!(" vaykrr {}", expr(cond)))
+                    .unwrap_or_default(),
+                self.block(body, depth + 1)
+            ),
+            Statement::Test { name, body } => format!(
+                "ngop fmetok {name} alu\n{}\n{indent}txew",
+                self.block(body, depth + 1)
+            ),
+        }
+    }
+}
+
+fn mod_use(ModUse { path, name }: &ModUse) -> String {
+    path.iter()
+        .map(|possessive| possessive.trim())
+        .chain([name.as_str()])
+        .fold("sar".to_owned(), |acc, word| format!("{acc} {word}"))
+}
+
+fn expr(AyNode { span, inner }: &AyNode<Expr>) -> String {
+    match inner {
+        Expr::FunCall { name, args } if args.is_empty() => format!("{name} si"),
+        Expr::FunCall { name, args } => format!(
+            "{name} fa {}",
+            args.iter().map(expr).collect::<Vec<String>>().join(" sì ")
+        ),
+        Expr::Array { items } if items.is_empty() => "vezeykoyu".to_owned(),
+        Expr::Array { items } => format!(
+            "vezeykoyu {}",
+            items.iter().map(expr).collect::<Vec<String>>().join(" sì ")
+        ),
+        Expr::Comparison {
+            left,
+            right,
+            operator,
+        } => format!(
+            "may' {} sì {} livu {}",
+            expr(left),
+            expr(right),
+            match operator {
+                ComparisonOperator::Equals => "teng",
+            }
+        ),
+        // Keeps the multiplier the number was written with
+        Expr::Number(_) => span
+            .as_str()
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" "),
+        Expr::String(string) => format!("san {string} sìk"),
+        Expr::Ident(name) => name.clone(),
+        Expr::Negated(node) => format!("ke {}", expr(node)),
+    }
+}
+
+/// Serializes an AST, dropping every span so that only its meaning is compared.
+fn without_spans<T: serde::Serialize>(ast: &T) -> Value {
+    fn strip(value: Value) -> Value {
+        match value {
+            Value::Object(map) => Value::Object(
+                map.into_iter()
+                    .filter(|(key, _)| key != "span")
+                    .map(|(key, value)| (key, strip(value)))
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(items.into_iter().map(strip).collect()),
+            other => other,
+        }
+    }
+
+    strip(serde_json::to_value(ast).expect("AST nodes only have string keys"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_examples_roundtrip() {
+        for entry in std::fs::read_dir("./examples").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|ext| ext != "ay") {
+                continue;
+            }
+
+            let source = std::fs::read_to_string(&path).unwrap();
+            if parsing::parse_module(source.as_ref()).is_err() {
+                continue;
+            }
+
+            let formatted = format_source(source.as_ref())
+                .unwrap_or_else(|trace| panic!("{}: {trace}", path.display()));
+            assert_eq!(
+                format_source(formatted.as_ref()).unwrap(),
+                formatted,
+                "{} is not formatted idempotently",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_comments_are_kept() {
+        let source = "''greeting''\nwìntxu fa san ''not a comment'' sìk. ''trailing\n";
+
+        assert_eq!(format_source(source).unwrap(), source);
+    }
+
+    #[test]
+    fn test_comments_ending_blocks() {
+        let source = "ngop lì'ukìng f alu\n    \
+                          ngop 'u x alu 1 ''allow: unused_variable''\n\
+                      txew. ''end\n\
+                      txo 1, tsakrr\n    \
+                          f si ''last''\n\
+                      txew.\n";
+
+        assert_eq!(format_source(source).unwrap(), source);
+    }
+
+    #[test]
+    fn test_trailing_comments_stay_on_their_line() {
+        let source = "ngop 'u x alu 1.    ''allow: unused_variable''\n\
+                      ngop lì'ukìng f alu\n\
+                      1  ulte ''first''\n\
+                      2 ''second\n\
+                      txew.\n\
+                      ''own line''\n\
+                      f si.\n";
+
+        assert_eq!(
+            format_source(source).unwrap(),
+            "ngop 'u x alu 1. ''allow: unused_variable''\n\
+             ngop lì'ukìng f alu\n    \
+                 1 ulte ''first''\n    \
+                 2 ''second\n\
+             txew.\n\
+             ''own line''\n\
+             f si.\n"
+        );
+    }
+}
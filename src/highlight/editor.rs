@@ -0,0 +1,240 @@
+use super::{
+    Category, CALL_MARKERS, CONTROLS, DECLARATORS, FUN_DECLARATOR, LITERALS, SEPARATORS,
+    STRING_END, STRING_START, TEST_DECLARATOR, VAR_DECLARATORS,
+};
+
+use clap::ValueEnum;
+use serde_json::json;
+
+const GRAMMAR: &str = include_str!("../../pest/grammar.pest");
+
+/// Rules whose terminals are trivia or pieces of names rather than keywords
+const SKIPPED_RULES: [&str; 5] = [
+    "WHITESPACE",
+    "COMMENT",
+    "NAVCHAR",
+    "possessive",
+    "possessive_ending",
+];
+
+/// Editor a syntax definition can be generated for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Editor {
+    /// TextMate grammar, also read by VS Code, Sublime Text and most highlighters
+    Textmate,
+    /// Vim syntax file
+    Vim,
+}
+
+impl Editor {
+    pub fn syntax(self) -> String {
+        match self {
+            Editor::Textmate => textmate(),
+            Editor::Vim => vim(),
+        }
+    }
+}
+
+/// Keyword categories, with the TextMate scope and Vim group of each
+const KEYWORDS: [(Category, &[&str], &str, &str); 4] = [
+    (
+        Category::Declarator,
+        &DECLARATORS,
+        "storage.type",
+        "Keyword",
+    ),
+    (
+        Category::Control,
+        &CONTROLS,
+        "keyword.control",
+        "Conditional",
+    ),
+    (
+        Category::Separator,
+        &SEPARATORS,
+        "keyword.operator",
+        "Operator",
+    ),
+    (Category::Number, &LITERALS, "constant.language", "Constant"),
+];
+
+/// Words of the grammar's terminals, e.g. `kem` and `si` for `"kem si"`.
+pub fn terminals() -> Vec<&'static str> {
+    let mut res = vec![];
+    let mut rule = "";
+
+    for line in GRAMMAR.lines() {
+        if let Some((name, _)) = line.split_once('=') {
+            if !line.starts_with(char::is_whitespace) {
+                rule = name.trim();
+            }
+        }
+        if SKIPPED_RULES.contains(&rule) || line.trim_start().starts_with("//") {
+            continue;
+        }
+
+        // Literals are between the odd and even quotes
+        res.extend(
+            line.split('"')
+                .skip(1)
+                .step_by(2)
+                .flat_map(str::split_whitespace)
+                .filter(|word| word.chars().count() > 1 && word.chars().all(is_name_char)),
+        );
+    }
+
+    res.sort_unstable();
+    res.dedup();
+    res
+}
+
+/// Terminals missing from the highlighter's keyword tables
+fn uncategorized() -> Vec<&'static str> {
+    terminals()
+        .into_iter()
+        .filter(|word| {
+            ![STRING_START, STRING_END].contains(word)
+                && !KEYWORDS
+                    .iter()
+                    .any(|(_, keywords, _, _)| keywords.contains(word))
+        })
+        .collect()
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_lowercase() || ['ì', 'ä', '\''].contains(&c)
+}
+
+pub fn textmate() -> String {
+    const BEFORE: &str = "(?<![a-zìä'])";
+    const AFTER: &str = "(?![a-zìä'])";
+    const NAME: &str = "[a-zìä']+(?:\\.[a-zìä']+)?";
+
+    let words = |words: &[&str]| format!("{BEFORE}(?:{}){AFTER}", words.join("|"));
+
+    let mut patterns = vec![
+        json!({ "name": "comment.line.aysinvi", "match": "''.*?(?:''|$)" }),
+        json!({
+            "name": "string.quoted.other.aysinvi",
+            "begin": format!("{BEFORE}{STRING_START}{AFTER}"),
+            "end": format!("(?<=\\s){STRING_END}"),
+        }),
+        json!({
+            "match": format!("{BEFORE}({FUN_DECLARATOR}|{TEST_DECLARATOR})\\s+({NAME})"),
+            "captures": {
+                "1": { "name": "storage.type.aysinvi" },
+                "2": { "name": "entity.name.function.aysinvi" },
+            },
+        }),
+        json!({
+            "match": format!("{BEFORE}({})\\s+({NAME})", VAR_DECLARATORS.join("|")),
+            "captures": {
+                "1": { "name": "storage.type.aysinvi" },
+                "2": { "name": "variable.other.declaration.aysinvi" },
+            },
+        }),
+        json!({
+            "name": "entity.name.function.call.aysinvi",
+            "match": format!("{BEFORE}{NAME}(?=\\s+{})", words(&CALL_MARKERS)),
+        }),
+    ];
+
+    patterns.extend(KEYWORDS.iter().map(|(_, keywords, scope, _)| {
+        json!({ "name": format!("{scope}.aysinvi"), "match": words(keywords) })
+    }));
+    if !uncategorized().is_empty() {
+        patterns.push(json!({ "name": "keyword.other.aysinvi", "match": words(&uncategorized()) }));
+    }
+    patterns.push(json!({
+        "name": "constant.numeric.aysinvi",
+        "match": format!("{BEFORE}[0-7]+{AFTER}"),
+    }));
+
+    let grammar = json!({
+        "name": "Aysìnvi",
+        "scopeName": "source.aysinvi",
+        "fileTypes": ["ay"],
+        "comment": "Generated by `aysinvi syntax textmate`, do not edit by hand",
+        "patterns": patterns,
+    });
+
+    serde_json::to_string_pretty(&grammar).expect("JSON values can always be turned into a string")
+        + "\n"
+}
+
+pub fn vim() -> String {
+    const NAME: &str = "[a-zìä']\\+\\(\\.[a-zìä']\\+\\)\\=";
+
+    let mut keywords = KEYWORDS
+        .iter()
+        .map(|(category, keywords, _, group)| {
+            (format!("{category:?}Keyword"), keywords.to_vec(), *group)
+        })
+        .collect::<Vec<_>>();
+    if !uncategorized().is_empty() {
+        keywords.push(("Keyword".to_owned(), uncategorized(), "Keyword"));
+    }
+
+    let mut res = "\" Vim syntax file\n\
+                   \" Language: Aysìnvi\n\
+                   \" Generated by `aysinvi syntax vim`, do not edit by hand\n\n\
+                   if exists(\"b:current_syntax\")\n  finish\nendif\n\n\
+                   \" Letters, digits, ', ì and ä\n\
+                   syn iskeyword @,48-57,39,236,228\n\n"
+        .to_owned();
+
+    for (name, words, _) in &keywords {
+        res += &format!("syn keyword aysinvi{name} {}\n", words.join(" "));
+    }
+    res += &format!(
+        "\nsyn match aysinviNumber \"\\<[0-7]\\+\\>\"\n\
+         syn match aysinviCall \"\\<{NAME}\\ze\\s\\+\\({}\\)\\>\"\n\
+         syn match aysinviDeclaration \"\\<\\({}\\)\\s\\+\\zs{NAME}\"\n\
+         syn region aysinviString start=\"\\<{STRING_START}\\>\" end=\"\\s\\zs{STRING_END}\"\n\
+         syn match aysinviComment \"''.\\{{-}}\\(''\\|$\\)\"\n\n",
+        CALL_MARKERS.join("\\|"),
+        [FUN_DECLARATOR, TEST_DECLARATOR]
+            .into_iter()
+            .chain(VAR_DECLARATORS)
+            .collect::<Vec<&str>>()
+            .join("\\|"),
+    );
+
+    for (name, _, group) in &keywords {
+        res += &format!("hi def link aysinvi{name} {group}\n");
+    }
+    for (name, group) in [
+        ("Number", "Number"),
+        ("Call", "Function"),
+        ("Declaration", "Identifier"),
+        ("String", "String"),
+        ("Comment", "Comment"),
+    ] {
+        res += &format!("hi def link aysinvi{name} {group}\n");
+    }
+
+    res + "\nlet b:current_syntax = \"aysinvi\"\n"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_keywords_in_sync() {
+        assert!(terminals().contains(&"kem"));
+        assert!(!terminals().contains(&"yä"));
+        assert_eq!(uncategorized(), Vec::<&str>::new());
+
+        // The checked in files are the generated ones
+        for (editor, path) in [
+            (Editor::Textmate, "editors/aysinvi.tmLanguage.json"),
+            (Editor::Vim, "editors/vim/syntax/aysinvi.vim"),
+        ] {
+            assert!(
+                std::fs::read_to_string(path).is_ok_and(|content| content == editor.syntax()),
+                "`{path}` is outdated, regenerate it with `aysinvi syntax`"
+            );
+        }
+    }
+}
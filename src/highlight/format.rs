@@ -0,0 +1,184 @@
+use super::{
+    theme::{Color, Style, Theme},
+    Category,
+};
+
+use std::ops::Range;
+
+use clap::ValueEnum;
+
+/// Output format of the highlighter
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Terminal escape codes
+    #[default]
+    Ansi,
+    /// `<span>`s with one CSS class per category, preceded by the theme's stylesheet
+    Html,
+    /// One color macro per category, used in an `alltt` environment
+    Latex,
+}
+
+impl Format {
+    pub fn render(
+        self,
+        source: &str,
+        categories: &[(Range<usize>, Category)],
+        theme: &Theme,
+    ) -> String {
+        let body = categories
+            .iter()
+            .map(|(range, category)| {
+                let text = &source[range.clone()];
+                match (self, theme.style(*category)) {
+                    (Format::Ansi, Some(style)) => format!("\x1b[{}m{text}\x1b[0m", ansi(style)),
+                    (Format::Ansi, None) => text.to_owned(),
+                    (Format::Html, _) if *category == Category::Plain => html_escape(text),
+                    (Format::Html, _) => format!(
+                        "<span class=\"ay-{}\">{}</span>",
+                        <&str>::from(category),
+                        html_escape(text)
+                    ),
+                    (Format::Latex, Some(_)) => {
+                        format!("\\{}{{{}}}", latex_macro(*category), latex_escape(text))
+                    }
+                    (Format::Latex, None) => latex_escape(text),
+                }
+            })
+            .collect::<String>();
+
+        match self {
+            Format::Ansi => body,
+            Format::Html => format!(
+                "<style>\n{}</style>\n<pre class=\"aysinvi\"><code>{body}</code></pre>\n",
+                theme
+                    .styles()
+                    .map(|(category, style)| format!(
+                        ".aysinvi .ay-{} {{ {}}}\n",
+                        <&str>::from(category),
+                        css(style)
+                    ))
+                    .collect::<String>()
+            ),
+            Format::Latex => format!(
+                "{}\\begin{{alltt}}\n{body}\\end{{alltt}}\n",
+                theme
+                    .styles()
+                    .map(|(category, style)| format!(
+                        "\\providecommand{{\\{}}}[1]{{{}}}\n",
+                        latex_macro(category),
+                        latex(style)
+                    ))
+                    .collect::<String>()
+            ),
+        }
+    }
+}
+
+pub(super) fn ansi(style: &Style) -> String {
+    let mut codes = vec![];
+    if style.bold {
+        codes.push("1".to_owned());
+    }
+    if style.italic {
+        codes.push("3".to_owned());
+    }
+    codes.push(match style.color {
+        Some(Color::Black) => "30".to_owned(),
+        Some(Color::Red) => "31".to_owned(),
+        Some(Color::Green) => "32".to_owned(),
+        Some(Color::Yellow) => "33".to_owned(),
+        Some(Color::Blue) => "34".to_owned(),
+        Some(Color::Magenta) => "35".to_owned(),
+        Some(Color::Cyan) => "36".to_owned(),
+        Some(Color::Gray) => "90".to_owned(),
+        Some(Color::Rgb(r, g, b)) => format!("38;2;{r};{g};{b}"),
+        None => "39".to_owned(),
+    });
+
+    codes.join(";")
+}
+
+fn css(style: &Style) -> String {
+    let mut res = String::new();
+    match style.color {
+        Some(Color::Rgb(r, g, b)) => res += &format!("color: #{r:02x}{g:02x}{b:02x}; "),
+        Some(color) => res += &format!("color: {}; ", color.name().unwrap_or_default()),
+        None => {}
+    }
+    if style.bold {
+        res += "font-weight: bold; ";
+    }
+    if style.italic {
+        res += "font-style: italic; ";
+    }
+    res
+}
+
+/// Body of a one argument macro applying `style`.
+fn latex(style: &Style) -> String {
+    let mut res = "#1".to_owned();
+    if style.bold {
+        res = format!("\\textbf{{{res}}}");
+    }
+    if style.italic {
+        res = format!("\\textit{{{res}}}");
+    }
+    match style.color {
+        Some(Color::Rgb(r, g, b)) => format!("\\textcolor[HTML]{{{r:02X}{g:02X}{b:02X}}}{{{res}}}"),
+        Some(color) => format!(
+            "\\textcolor{{{}}}{{{res}}}",
+            color.name().unwrap_or_default()
+        ),
+        None => res,
+    }
+}
+
+/// `Ay` followed by the category, e.g. `AyCall`
+fn latex_macro(category: Category) -> String {
+    format!("Ay{category:?}")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Only backslashes and braces are special in `alltt`.
+fn latex_escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_owned(),
+            '{' => "\\{".to_owned(),
+            '}' => "\\}".to_owned(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::highlight::highlight;
+
+    #[test]
+    fn test_formats() {
+        let theme = Theme::default();
+        let source = "wìntxu fa 1.";
+
+        assert_eq!(
+            highlight(source, Format::Ansi, &theme),
+            "\x1b[32mwìntxu\x1b[0m \x1b[36mfa\x1b[0m \x1b[31m1\x1b[0m."
+        );
+        assert!(highlight(source, Format::Html, &theme).contains(
+            "<pre class=\"aysinvi\"><code><span class=\"ay-call\">wìntxu</span> \
+             <span class=\"ay-separator\">fa</span> <span class=\"ay-number\">1</span>.</code></pre>"
+        ));
+        assert!(highlight(source, Format::Latex, &theme)
+            .contains("\\providecommand{\\AyCall}[1]{\\textcolor{green}{#1}}\n"));
+        assert!(highlight(source, Format::Latex, &theme)
+            .contains("\\AyCall{wìntxu} \\AySeparator{fa} \\AyNumber{1}."));
+    }
+}
@@ -0,0 +1,298 @@
+pub mod editor;
+pub mod format;
+pub mod render;
+pub mod theme;
+
+use crate::ast::{
+    cst::{self, CstNode, TokenKind},
+    parsing::Rule,
+};
+
+use std::ops::Range;
+
+pub use {
+    format::Format,
+    render::{ColorChoice, Renderer},
+    theme::Theme,
+};
+
+use {serde::Deserialize, strum_macros::IntoStaticStr};
+
+/// What a piece of source code is, as far as coloring goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Category {
+    Comment,
+    /// Declaration keywords: `ngop`, `'u`, `alu`, `lì'ukìng`, `sar`...
+    Declarator,
+    /// Control flow keywords: `txo`, `leyn`, `ftang`...
+    Control,
+    /// Keywords separating arguments and items: `fa`, `sì`, `si`, `ulte`...
+    Separator,
+    /// Name being declared, be it a function, a parameter or a variable
+    Declaration,
+    /// Name of the function being called
+    Call,
+    Identifier,
+    String,
+    Number,
+    /// Whitespace and punctuation
+    Plain,
+}
+
+const DECLARATORS: [&str; 10] = [
+    "ngop",
+    "'u",
+    "meu",
+    "pxeu",
+    "ayu",
+    "alu",
+    "txew",
+    "lì'ukìng",
+    "fmetok",
+    "sar",
+];
+const CONTROLS: [&str; 7] = [
+    "txo",
+    "tsakrr",
+    "txokefyaw",
+    "leyn",
+    "vaykrr",
+    "kem",
+    "ftang",
+];
+const SEPARATORS: [&str; 7] = ["fa", "si", "sì", "ulte", "ke", "may'", "livu"];
+const LITERALS: [&str; 4] = ["melo", "pxelo", "teng", "vezeykoyu"];
+
+/// Keywords followed by the name they declare
+const FUN_DECLARATOR: &str = "lì'ukìng";
+const TEST_DECLARATOR: &str = "fmetok";
+const VAR_DECLARATORS: [&str; 4] = ["'u", "meu", "pxeu", "ayu"];
+const STRING_START: &str = "san";
+const STRING_END: &str = "sìk";
+/// Keywords following the name of the function called
+const CALL_MARKERS: [&str; 2] = ["fa", "si"];
+
+/// Colors `source` with the renderer of diagnostics, leaving it as is when colors are off.
+pub fn highlight_aysinvi(source: &str) -> String {
+    render::current().code(source)
+}
+
+/// Renders `source` in `format`, styling each category as `theme` says.
+pub fn highlight(source: &str, format: Format, theme: &Theme) -> String {
+    format.render(source, &categorize(source), theme)
+}
+
+/// Splits `source` into consecutive categorized ranges covering all of it.
+///
+/// Programs are categorized from their syntax tree, anything that does not parse (a line being
+/// typed, an excerpt in an error) by a lexer that only knows keywords, strings and comments.
+pub fn categorize(source: &str) -> Vec<(Range<usize>, Category)> {
+    let Ok(cst) = cst::parse(source) else {
+        return lex(source, 0..source.len());
+    };
+
+    // Names and literals come from rules, the rest from the tokens between them
+    let mut rules = vec![];
+    cst.nodes()
+        .for_each(|node| rule_categories(source, node, None, &mut rules));
+    rules.sort_by_key(|(range, _)| range.start);
+
+    let mut rules = rules.into_iter().peekable();
+    let mut res = vec![];
+
+    for token in cst.tokens() {
+        let range = token.span.start()..token.span.end();
+
+        // Tokens inside a rule already categorized, e.g. `san` and `sìk` around a string
+        if res
+            .last()
+            .is_some_and(|(last, _): &(Range<usize>, Category)| range.end <= last.end)
+        {
+            continue;
+        }
+
+        if let Some((rule, category)) = rules.next_if(|(rule, _)| rule.start <= range.start) {
+            res.push((rule, category));
+            continue;
+        }
+
+        let category = match token.kind {
+            TokenKind::Comment => Category::Comment,
+            TokenKind::Keyword => keyword(token.span.as_str()).unwrap_or(Category::Identifier),
+            TokenKind::Literal => Category::Identifier,
+            TokenKind::Whitespace | TokenKind::Punctuation => Category::Plain,
+        };
+        res.push((range, category));
+    }
+
+    res
+}
+
+/// Categories of the names and literals under `node`, `parent` being the rule it belongs to.
+fn rule_categories(
+    source: &str,
+    node: &CstNode,
+    parent: Option<Rule>,
+    res: &mut Vec<(Range<usize>, Category)>,
+) {
+    let span = node.pair().as_span();
+    let range = span.start()..span.end();
+
+    match (parent, node.rule()) {
+        (_, Rule::number) => res.push((range, Category::Number)),
+        (_, Rule::comparison_operator) => res.push((range, Category::Separator)),
+        (_, Rule::expr) if node.nodes().any(|child| child.rule() == Rule::string) => {
+            res.push((range, Category::String))
+        }
+        // Atomic, so its items have no rules of their own
+        (_, Rule::array) => res.extend(lex(source, range)),
+        (Some(Rule::fun_call), Rule::ident) => res.push((range, Category::Call)),
+        (
+            Some(Rule::fun_dec | Rule::fun_dec_args | Rule::var_dec | Rule::test_dec),
+            Rule::ident | Rule::fun_ident,
+        ) => res.push((range, Category::Declaration)),
+        (_, rule) => node
+            .nodes()
+            .for_each(|child| rule_categories(source, child, Some(rule), res)),
+    }
+}
+
+fn keyword(word: &str) -> Option<Category> {
+    if DECLARATORS.contains(&word) {
+        Some(Category::Declarator)
+    } else if CONTROLS.contains(&word) {
+        Some(Category::Control)
+    } else if SEPARATORS.contains(&word) {
+        Some(Category::Separator)
+    } else if LITERALS.contains(&word) || word.chars().all(|c| c.is_ascii_digit()) {
+        Some(Category::Number)
+    } else {
+        None
+    }
+}
+
+/// Categorizes `range` of `source` word by word, following the grammar's lexical rules for
+/// comments and strings, and guessing declarations and calls from the keywords around them.
+fn lex(source: &str, range: Range<usize>) -> Vec<(Range<usize>, Category)> {
+    let mut res: Vec<(Range<usize>, Category)> = vec![];
+    let mut offset = range.start;
+    // Last word, to spot the name following a declarator
+    let mut previous: Option<&str> = None;
+
+    while offset < range.end {
+        let rest = &source[offset..range.end];
+        let first = rest.chars().next().unwrap();
+
+        let (len, category) = if let Some(content) = rest.strip_prefix("''") {
+            let newline = content.find(['\n', '\r']).unwrap_or(content.len());
+            let len = match content.find("''") {
+                Some(quotes) if quotes < newline => quotes + 2,
+                _ => newline,
+            };
+            (len + 2, Category::Comment)
+        } else if first.is_whitespace() || [',', '.'].contains(&first) {
+            let len = rest
+                .find(|c: char| !c.is_whitespace() && ![',', '.'].contains(&c))
+                .unwrap_or(rest.len());
+            (len, Category::Plain)
+        } else {
+            // Dots between letters belong to infix function names like `t.aron`
+            let end = rest
+                .char_indices()
+                .find(|(index, c)| {
+                    c.is_whitespace()
+                        || *c == ','
+                        || *c == '.'
+                            && !rest[index + 1..]
+                                .starts_with(|c: char| c.is_alphabetic() || c == '\'')
+                })
+                .map_or(rest.len(), |(index, _)| index);
+            let word = &rest[..end];
+            let word = word.find("''").map_or(word, |quotes| &word[..quotes]);
+
+            if word == STRING_START {
+                // Strings run until whitespace followed by `sìk`, or the end of the input
+                let end = rest
+                    .match_indices(STRING_END)
+                    .find(|(index, _)| rest[..*index].ends_with(char::is_whitespace))
+                    .map_or(rest.len(), |(index, sìk)| index + sìk.len());
+                (end, Category::String)
+            } else {
+                // Declared names may look like keywords, called ones are followed by `fa` or `si`
+                let next = rest[word.len()..].split_whitespace().next();
+                let category = match previous {
+                    Some(previous)
+                        if [FUN_DECLARATOR, TEST_DECLARATOR].contains(&previous)
+                            || VAR_DECLARATORS.contains(&previous) =>
+                    {
+                        Category::Declaration
+                    }
+                    _ => keyword(word).unwrap_or(
+                        if next.is_some_and(|next| CALL_MARKERS.contains(&next)) {
+                            Category::Call
+                        } else {
+                            Category::Identifier
+                        },
+                    ),
+                };
+                previous = Some(word);
+                (word.len(), category)
+            }
+        };
+
+        res.push((offset..offset + len, category));
+        offset += len;
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn categories(source: &str) -> Vec<(&str, Category)> {
+        categorize(source)
+            .into_iter()
+            .filter(|(_, category)| *category != Category::Plain)
+            .map(|(range, category)| (&source[range], category))
+            .collect()
+    }
+
+    #[test]
+    fn test_categorize() {
+        use Category::*;
+
+        let source = "ngop 'u txo alu wìntxu fa san ke asìk, tsa sìk. ''txo\n";
+        assert_eq!(
+            categories(source),
+            [
+                ("ngop", Declarator),
+                ("'u", Declarator),
+                ("txo", Declaration),
+                ("alu", Declarator),
+                ("wìntxu", Call),
+                ("fa", Separator),
+                ("san ke asìk, tsa sìk", String),
+                ("''txo", Comment),
+            ]
+        );
+        assert_eq!(categorize(source).last().unwrap().0.end, source.len());
+
+        // Unterminated input is lexed the same way
+        assert_eq!(
+            categories("ngop 'u txo alu wìntxu fa san ke sìk"),
+            [
+                ("ngop", Declarator),
+                ("'u", Declarator),
+                ("txo", Declaration),
+                ("alu", Declarator),
+                ("wìntxu", Call),
+                ("fa", Separator),
+                ("san ke sìk", String),
+            ]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use super::{format::ansi, highlight, Format, Theme};
+
+use std::{io::IsTerminal, sync::OnceLock};
+
+use {clap::ValueEnum, serde::Deserialize};
+
+/// Whether to color the output, `auto` coloring terminals only
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color terminals, unless the `NO_COLOR` environment variable is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Environment variable disabling colors when set to anything but the empty string, see
+/// <https://no-color.org>
+pub const NO_COLOR_VAR: &str = "NO_COLOR";
+
+impl ColorChoice {
+    /// Whether to color what is written to `stream`.
+    pub fn enabled(self, stream: &impl IsTerminal) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os(NO_COLOR_VAR).is_none_or(|value| value.is_empty())
+                    && stream.is_terminal()
+            }
+        }
+    }
+}
+
+/// Part of a diagnostic styled on its own, next to the highlighted code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Line numbers, pipes and elisions around the code
+    Gutter,
+    /// Compiler stage an error comes from
+    Stage,
+    /// Line and column of an error
+    Location,
+    /// Error codes, severities and the marks under the code in error
+    Error,
+    Warning,
+    /// Marks under the code labels point at
+    Secondary,
+    /// `note:` and `help:`
+    Emphasis,
+}
+
+/// How diagnostics and code excerpts are styled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Renderer {
+    /// Text as is, for files, pipes and `NO_COLOR`
+    Plain,
+    /// Terminal escape codes following a theme, the default one or a custom one
+    Ansi(Theme),
+}
+
+static CURRENT: OnceLock<Renderer> = OnceLock::new();
+
+/// Chooses the renderer of every diagnostic, ignored once one was rendered.
+pub fn set(renderer: Renderer) {
+    let _ = CURRENT.set(renderer);
+}
+
+/// Renderer chosen by [`set`], otherwise the default theme when the standard error is a terminal
+pub fn current() -> &'static Renderer {
+    CURRENT.get_or_init(|| Renderer::new(ColorChoice::Auto, &std::io::stderr(), Theme::default()))
+}
+
+impl Renderer {
+    /// Renderer of what is written to `stream`, with `theme` if it is to be colored.
+    pub fn new(choice: ColorChoice, stream: &impl IsTerminal, theme: Theme) -> Self {
+        if choice.enabled(stream) {
+            Renderer::Ansi(theme)
+        } else {
+            Renderer::Plain
+        }
+    }
+
+    /// Styles `text` as `role`.
+    pub fn paint(&self, role: Role, text: &str) -> String {
+        match self {
+            Renderer::Ansi(theme) => match theme.role(role) {
+                Some(style) => format!("\x1b[{}m{text}\x1b[0m", ansi(style)),
+                None => text.to_owned(),
+            },
+            Renderer::Plain => text.to_owned(),
+        }
+    }
+
+    /// Highlights the code in `source`.
+    pub fn code(&self, source: &str) -> String {
+        match self {
+            Renderer::Ansi(theme) => highlight(source, Format::Ansi, theme),
+            Renderer::Plain => source.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_renderers() {
+        assert!(ColorChoice::Always.enabled(&std::io::stdout()));
+        assert!(!ColorChoice::Never.enabled(&std::io::stdout()));
+
+        let code = "wìntxu fa 1.";
+        assert_eq!(Renderer::Plain.code(code), code);
+        assert_eq!(Renderer::Plain.paint(Role::Error, "^^^"), "^^^");
+        assert_eq!(
+            Renderer::Ansi(Theme::default()).paint(Role::Error, "^^^"),
+            "\x1b[1;31m^^^\x1b[0m"
+        );
+        assert!(Renderer::Ansi(Theme::default()).code(code).contains('\x1b'));
+    }
+}
@@ -0,0 +1,169 @@
+use super::{render::Role, Category};
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+/// Style of each category and diagnostic role, those without one being left as is
+///
+/// Theme files are JSON objects mapping category and role names to styles, overriding the
+/// default theme:
+///
+/// ```json
+/// { "comment": { "color": "#7f7f7f", "italic": true }, "error": { "color": "magenta" } }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    styles: BTreeMap<Category, Style>,
+    roles: BTreeMap<Role, Style>,
+}
+
+/// Name of a theme file entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(untagged)]
+enum Key {
+    Category(Category),
+    Role(Role),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub italic: bool,
+}
+
+/// One of the eight basic colors every output format knows by name, or an exact one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    Gray,
+    Rgb(u8, u8, u8),
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        let style = |color, bold| Style {
+            color: Some(color),
+            bold,
+            italic: false,
+        };
+        let bold = Style {
+            bold: true,
+            ..Default::default()
+        };
+
+        Theme {
+            styles: BTreeMap::from([
+                (Category::Comment, style(Color::Gray, false)),
+                (Category::Declarator, style(Color::Yellow, false)),
+                (Category::Control, style(Color::Magenta, false)),
+                (Category::Separator, style(Color::Cyan, false)),
+                (Category::Declaration, style(Color::Yellow, true)),
+                (Category::Call, style(Color::Green, false)),
+                (Category::Identifier, style(Color::Blue, true)),
+                (Category::String, style(Color::Red, false)),
+                (Category::Number, style(Color::Red, false)),
+            ]),
+            roles: BTreeMap::from([
+                (Role::Gutter, style(Color::Gray, false)),
+                (Role::Stage, style(Color::Yellow, false)),
+                (Role::Location, style(Color::Blue, false)),
+                (Role::Error, style(Color::Red, true)),
+                (Role::Warning, style(Color::Yellow, true)),
+                (Role::Secondary, style(Color::Blue, true)),
+                (Role::Emphasis, bold),
+            ]),
+        }
+    }
+}
+
+impl Theme {
+    /// Default theme overridden by the styles of a theme file.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read theme `{path}`: {err}"))?;
+        let styles: BTreeMap<Key, Style> = serde_json::from_str(&content)
+            .map_err(|err| format!("Invalid theme `{path}`: {err}"))?;
+
+        let mut theme = Theme::default();
+        for (key, style) in styles {
+            match key {
+                Key::Category(category) => theme.styles.insert(category, style),
+                Key::Role(role) => theme.roles.insert(role, style),
+            };
+        }
+        Ok(theme)
+    }
+
+    pub fn style(&self, category: Category) -> Option<&Style> {
+        self.styles.get(&category)
+    }
+
+    pub fn role(&self, role: Role) -> Option<&Style> {
+        self.roles.get(&role)
+    }
+
+    pub fn styles(&self) -> impl Iterator<Item = (Category, &Style)> {
+        self.styles
+            .iter()
+            .map(|(category, style)| (*category, style))
+    }
+}
+
+impl Color {
+    /// Name shared by CSS and LaTeX's `xcolor`, `None` for exact colors.
+    pub fn name(self) -> Option<&'static str> {
+        Some(match self {
+            Color::Black => "black",
+            Color::Red => "red",
+            Color::Green => "green",
+            Color::Yellow => "yellow",
+            Color::Blue => "blue",
+            Color::Magenta => "magenta",
+            Color::Cyan => "cyan",
+            Color::Gray => "gray",
+            Color::Rgb(..) => return None,
+        })
+    }
+}
+
+impl TryFrom<String> for Color {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        if let Some(hex) = value.strip_prefix('#') {
+            let channel = |index: usize| {
+                hex.get(index..index + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("'{value}' is not a #rrggbb color")),
+            };
+        }
+
+        [
+            Color::Black,
+            Color::Red,
+            Color::Green,
+            Color::Yellow,
+            Color::Blue,
+            Color::Magenta,
+            Color::Cyan,
+            Color::Gray,
+        ]
+        .into_iter()
+        .find(|color| color.name() == Some(value.as_str()))
+        .ok_or_else(|| format!("Unknown color '{value}'"))
+    }
+}
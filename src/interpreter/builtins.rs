@@ -0,0 +1,100 @@
+use super::value::Value;
+
+use crate::error::kind::RunningError;
+
+use std::io::Write;
+
+/// Functions available without being declared
+pub const BUILTINS: &[&str] = &["wìntxu", "sung", "luke", "'ì'awnyu", "teng", "nìngay"];
+
+/// Calls the builtin `name`, returning `None` if there is no such builtin.
+pub fn call(
+    name: &str,
+    args: Vec<Value>,
+    output: &mut dyn Write,
+) -> Option<Result<Value, RunningError>> {
+    let res = match name {
+        // Show
+        "wìntxu" => writeln!(
+            output,
+            "{}",
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+        .map(|_| Value::Unit)
+        .map_err(|err| RunningError::Output {
+            reason: err.to_string(),
+        }),
+        // Add
+        "sung" => args
+            .into_iter()
+            .map(Ok)
+            .reduce(|acc, arg| add(acc?, arg?))
+            .unwrap_or(Ok(Value::Number(0))),
+        // Without
+        "luke" => numbers(name, &args).and_then(|numbers| match numbers[..] {
+            [first, ref rest @ ..] => Ok(Value::Number(
+                rest.iter()
+                    .fold(first, |acc, number| acc.wrapping_sub(*number)),
+            )),
+            [] => Err(arity(name, 0)),
+        }),
+        // Remainder
+        "'ì'awnyu" => numbers(name, &args).and_then(|numbers| match numbers[..] {
+            [_, 0] => Err(RunningError::DivisionByZero),
+            [left, right] => Ok(Value::Number(left.wrapping_rem(right))),
+            _ => Err(arity(name, numbers.len())),
+        }),
+        // Same
+        "teng" => match &args[..] {
+            [first, rest @ ..] => Ok(Value::Bool(rest.iter().all(|arg| arg == first))),
+            [] => Err(arity(name, 0)),
+        },
+        // Truly, fails the test it is in unless its argument holds
+        "nìngay" => match &args[..] {
+            [cond] if cond.is_truthy() => Ok(Value::Unit),
+            [_] => Err(RunningError::AssertionFailed),
+            _ => Err(arity(name, args.len())),
+        },
+        _ => return None,
+    };
+
+    Some(res)
+}
+
+fn add(left: Value, right: Value) -> Result<Value, RunningError> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => Ok(Value::Number(left.wrapping_add(right))),
+        (Value::String(left), Value::String(right)) => Ok(Value::String(left + &right)),
+        (Value::Array(mut left), Value::Array(right)) => {
+            left.extend(right);
+            Ok(Value::Array(left))
+        }
+        (left, right) => Err(RunningError::CannotAdd {
+            left: left.type_name(),
+            right: right.type_name(),
+        }),
+    }
+}
+
+fn numbers(name: &str, args: &[Value]) -> Result<Vec<i64>, RunningError> {
+    args.iter()
+        .map(|arg| match arg {
+            Value::Number(number) => Ok(*number),
+            other => Err(RunningError::NonNumberArgument {
+                function: name.to_owned(),
+                found: other.type_name(),
+            }),
+        })
+        .collect()
+}
+
+fn arity(name: &str, found: usize) -> RunningError {
+    RunningError::ArgumentCount {
+        function: name.to_owned(),
+        expected: None,
+        found,
+    }
+}
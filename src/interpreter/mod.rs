@@ -0,0 +1,3 @@
+pub mod builtins;
+pub mod runtime;
+pub mod value;
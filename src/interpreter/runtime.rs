@@ -0,0 +1,321 @@
+use super::{builtins, value::Value};
+
+use crate::{
+    ast::{
+        binding::{conjugations, Expr, FunDec, Statement, VarDec},
+        lib::{AyNode, ComparisonOperator},
+    },
+    error::{
+        kind::{BindingError, ErrorKind, RunningError},
+        span::Span,
+        trace::{Stage, Trace},
+        trace_error::Error,
+    },
+};
+
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+/// Tree-walking interpreter over the bound AST.
+///
+/// Declarations are kept between calls to `run`, which is what the REPL relies on.
+pub struct Interpreter {
+    scope: Scope,
+    output: Box<dyn Write>,
+}
+
+/// Every declaration visible from a point of the program, innermost first.
+///
+/// Scopes are never changed once built, a declaration making a new one on top of the previous
+/// scope. Functions can thus keep the scope they are declared in and run their body there, which
+/// is how binding resolves the names they use.
+#[derive(Clone, Default)]
+struct Scope(Option<Rc<Declaration>>);
+
+struct Declaration {
+    name: String,
+    symbol: Symbol,
+    outer: Scope,
+}
+
+enum Symbol {
+    Var(Value),
+    /// Function along with the scope it was declared in
+    Fun(Rc<FunDec>, Scope),
+}
+
+impl Scope {
+    fn with(&self, name: &str, symbol: Symbol) -> Self {
+        Self(Some(Rc::new(Declaration {
+            name: name.to_owned(),
+            symbol,
+            outer: self.clone(),
+        })))
+    }
+
+    fn declarations(&self) -> impl Iterator<Item = &Declaration> {
+        std::iter::successors(self.0.as_deref(), |declaration| {
+            declaration.outer.0.as_deref()
+        })
+    }
+
+    fn var(&self, name: &str) -> Option<&Value> {
+        self.declarations()
+            .find_map(|declaration| match &declaration.symbol {
+                Symbol::Var(value) if declaration.name == name => Some(value),
+                _ => None,
+            })
+    }
+
+    /// Function called `name` in any of its tenses, along with the scope it was declared in.
+    fn fun(&self, name: &str) -> Option<(Rc<FunDec>, Scope)> {
+        self.declarations()
+            .find_map(|declaration| match &declaration.symbol {
+                Symbol::Fun(dec, scope)
+                    if conjugations(&declaration.name)
+                        .iter()
+                        .any(|(form, _)| form == name) =>
+                {
+                    Some((dec.clone(), scope.clone()))
+                }
+                _ => None,
+            })
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::with_output(Box::new(std::io::stdout()))
+    }
+}
+
+impl Interpreter {
+    pub fn with_output(output: Box<dyn Write>) -> Self {
+        Self {
+            scope: Scope::default(),
+            output,
+        }
+    }
+
+    /// Runs every statement, returning the value of the last one.
+    pub fn run(&mut self, ast: &[AyNode<Statement>]) -> Result<Value, Trace> {
+        ast.iter()
+            .try_fold(Value::Unit, |_, statement| self.exec(statement))
+    }
+
+    /// Runs `actions` in `scope`, going back to the current scope afterwards.
+    fn scoped<T>(&mut self, scope: Scope, actions: impl FnOnce(&mut Self) -> T) -> T {
+        let outer = std::mem::replace(&mut self.scope, scope);
+
+        let res = actions(self);
+
+        self.scope = outer;
+
+        res
+    }
+
+    fn exec(&mut self, AyNode { inner, .. }: &AyNode<Statement>) -> Result<Value, Trace> {
+        match inner {
+            Statement::FunDec(dec) => {
+                self.scope = self.scope.with(
+                    &dec.name,
+                    Symbol::Fun(Rc::new(dec.clone()), self.scope.clone()),
+                );
+                Ok(Value::Unit)
+            }
+            Statement::VarDec(VarDec { names, values }) => {
+                let values = values
+                    .iter()
+                    .map(|value| self.eval(value))
+                    .collect::<Result<Vec<Value>, Trace>>()?;
+
+                names.iter().zip(values).for_each(|(name, value)| {
+                    self.scope = self.scope.with(name, Symbol::Var(value))
+                });
+
+                Ok(Value::Unit)
+            }
+            Statement::Expr(expr) => self.eval(expr),
+            Statement::If {
+                cond,
+                then,
+                otherwise,
+            } => {
+                let branch = if self.eval(cond)?.is_truthy() {
+                    then
+                } else {
+                    otherwise
+                };
+
+                self.scoped(self.scope.clone(), |interpreter| interpreter.run(branch))
+            }
+            Statement::Loop { cond, body } => {
+                // `vaykrr` means "until"
+                while !cond
+                    .as_ref()
+                    .map(|cond| self.eval(cond))
+                    .transpose()?
+                    .is_some_and(|cond| cond.is_truthy())
+                {
+                    self.scoped(self.scope.clone(), |interpreter| interpreter.run(body))?;
+                }
+
+                Ok(Value::Unit)
+            }
+            // Only run by `aysinvi test`, through `run` on its body
+            Statement::Test { .. } => Ok(Value::Unit),
+        }
+    }
+
+    fn eval(&mut self, AyNode { span, inner }: &AyNode<Expr>) -> Result<Value, Trace> {
+        match inner {
+            Expr::FunCall { name, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.eval(arg))
+                    .collect::<Result<Vec<Value>, Trace>>()?;
+
+                self.call(span, name, args)
+            }
+            Expr::Array { items } => Ok(Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.eval(item))
+                    .collect::<Result<Vec<Value>, Trace>>()?,
+            )),
+            Expr::Comparison {
+                left,
+                right,
+                operator,
+            } => {
+                let (left, right) = (self.eval(left)?, self.eval(right)?);
+
+                Ok(Value::Bool(match operator {
+                    ComparisonOperator::Equals => left == right,
+                }))
+            }
+            Expr::Number(number) => Ok(Value::Number(*number)),
+            Expr::String(string) => Ok(Value::String(string.clone())),
+            Expr::Var(name) => self.scope.var(name).cloned().ok_or_else(|| {
+                error(
+                    span,
+                    BindingError::UndefinedVariable {
+                        name: name.clone(),
+                        candidates: vec![],
+                    },
+                )
+            }),
+            Expr::Negated(expr) => match self.eval(expr)? {
+                Value::Bool(boolean) => Ok(Value::Bool(!boolean)),
+                Value::Number(number) => Ok(Value::Number((number == 0) as i64)),
+                Value::Array(items) => Ok(Value::Bool(items.is_empty())),
+                other => Err(error(
+                    span,
+                    RunningError::CannotNegate {
+                        found: other.type_name(),
+                    },
+                )),
+            },
+            // Programs with binding errors are never run
+            Expr::Error => Err(error(span, RunningError::Unbound)),
+        }
+    }
+
+    fn call(&mut self, span: &Span, name: &str, args: Vec<Value>) -> Result<Value, Trace> {
+        let Some((dec, scope)) = self.scope.fun(name) else {
+            return match builtins::call(name, args, &mut self.output) {
+                Some(res) => res.map_err(|err| error(span, err)),
+                None => Err(error(
+                    span,
+                    BindingError::UndefinedFunction {
+                        name: name.to_owned(),
+                        candidates: vec![],
+                    },
+                )),
+            };
+        };
+
+        if dec.args.len() != args.len() {
+            return Err(error(
+                span,
+                RunningError::ArgumentCount {
+                    function: name.to_owned(),
+                    expected: Some(dec.args.len()),
+                    found: args.len(),
+                },
+            ));
+        }
+
+        // The body sees the scope of the declaration, the function itself and its arguments
+        let scope = dec.args.iter().zip(args).fold(
+            scope.with(&dec.name, Symbol::Fun(dec.clone(), scope.clone())),
+            |scope, (name, value)| scope.with(name, Symbol::Var(value)),
+        );
+
+        self.scoped(scope, |interpreter| interpreter.run(&dec.body))
+    }
+}
+
+/// Output sink that can still be read once handed to an interpreter
+#[derive(Clone, Default)]
+pub struct Capture(Rc<RefCell<Vec<u8>>>);
+
+impl Capture {
+    /// Everything written so far, leaving the capture empty
+    pub fn take(&self) -> String {
+        String::from_utf8_lossy(&self.0.take()).into_owned()
+    }
+}
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn error(span: &Span, kind: impl Into<ErrorKind>) -> Trace {
+    Trace::new(Stage::Running, Error::new(span.clone(), kind))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ast::{binding, lib::SourceCode, parsing};
+
+    fn run(source: &str) -> (Result<Value, Trace>, String) {
+        let capture = Capture::default();
+        let mut interpreter = Interpreter::with_output(Box::new(capture.clone()));
+
+        let ast = parsing::parse(SourceCode::Content(source.to_owned())).unwrap();
+        let res = interpreter.run(&binding::convert(&ast).unwrap());
+
+        (res, capture.take())
+    }
+
+    #[test]
+    fn test_lexical_scope() {
+        // `f` uses the `a` it was declared next to, not the parameter of its caller
+        let (res, output) = run("ngop 'u a alu 1.\n\
+             ngop lì'ukìng f alu a txew.\n\
+             ngop lì'ukìng g fa a alu f si txew.\n\
+             wìntxu fa g fa 2.\n\
+             ngop 'u a alu 3.\n\
+             f si.");
+
+        assert_eq!(res.unwrap(), Value::Number(1));
+        assert_eq!(output, "1\n");
+    }
+
+    #[test]
+    fn test_conjugated_call() {
+        let (res, output) = run("ngop lì'ukìng t.aron fa a alu wìntxu fa a txew.\n\
+             tìyaron fa san kaltxì sìk.\n\
+             sung fa 7 sì 1.");
+
+        assert_eq!(res.unwrap(), Value::Number(8));
+        assert_eq!(output, "kaltxì\n");
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Display;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Value {
+    #[default]
+    Unit,
+    Bool(bool),
+    Number(i64),
+    String(String),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Unit => false,
+            Value::Bool(boolean) => *boolean,
+            Value::Number(number) => *number != 0,
+            Value::String(string) => !string.is_empty(),
+            Value::Array(items) => !items.is_empty(),
+        }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Unit => "Unit",
+            Value::Bool(_) => "Bool",
+            Value::Number(_) => "Number",
+            Value::String(_) => "String",
+            Value::Array(_) => "Array",
+        }
+    }
+}
+
+impl Display for Value {
+    /// Numbers are written in octal, like they are in source code.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Unit => Ok(()),
+            Value::Bool(true) => write!(f, "srane"),
+            Value::Bool(false) => write!(f, "kehe"),
+            Value::Number(number) if *number < 0 => write!(f, "-{:o}", number.unsigned_abs()),
+            Value::Number(number) => write!(f, "{number:o}"),
+            Value::String(string) => write!(f, "{string}"),
+            Value::Array(items) => write!(
+                f,
+                "vezeykoyu {}",
+                items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" sì ")
+            ),
+        }
+    }
+}
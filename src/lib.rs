@@ -0,0 +1,14 @@
+pub mod ast;
+pub mod emit;
+pub mod error;
+pub mod fix;
+pub mod formatter;
+pub mod highlight;
+pub mod interpreter;
+pub mod lsp;
+pub mod repl;
+pub mod testing;
+
+extern crate pest;
+#[macro_use]
+extern crate pest_derive;
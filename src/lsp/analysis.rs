@@ -0,0 +1,513 @@
+use crate::{
+    ast::{
+        binding::{self, Reference, References, Scopes},
+        lib::{AyNode, AyType, SourceFile},
+        parsing::{self, Statement as PStatement},
+        typing::{self, Expr, FunDec, Statement, TypedExpr, VarDec},
+    },
+    error::{
+        code::Code,
+        kind::{ErrorKind, ParsingError},
+        lint::{Level, Lint, Warning, Warnings},
+        span::Span,
+        trace::{Stage, Trace, TraceError},
+        trace_error::{Error, Suggestion},
+    },
+    interpreter::builtins::BUILTINS,
+};
+
+use std::ops::Range;
+
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString, Position, TextEdit,
+};
+use pest::error::LineColLocation;
+
+/// Everything the server knows about an open document, recomputed on every change.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    /// Path of the document, `None` for unsaved buffers
+    pub file: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Names of the document and of every module it imports
+    pub references: Vec<Reference>,
+    /// Hover text by byte range in the document
+    pub hovers: Vec<(Range<usize>, String)>,
+    /// Inferred types of the variables declared in the document, by name range
+    pub types: Vec<(Range<usize>, String)>,
+    /// Functions that can be called, `None` if the document does not parse
+    pub functions: Option<Vec<Function>>,
+}
+
+/// Function visible in part of the document
+#[derive(Debug, Clone)]
+pub struct Function {
+    /// Name as declared, e.g. `t.aron`
+    pub name: String,
+    /// `None` for builtins, which take any number of arguments
+    pub args: Option<Vec<String>>,
+    /// Byte range of the document the function can be called from
+    pub scope: Range<usize>,
+}
+
+pub fn analyse(file: Option<&str>, text: &str) -> Analysis {
+    let mut analysis = Analysis {
+        file: file.map(str::to_owned),
+        ..Default::default()
+    };
+
+    let mut warnings = Warnings::default();
+    warnings.file = analysis.file.clone();
+    let (uses, ast) = match parsing::parse_module_with(text, &mut warnings) {
+        Ok(module) => module,
+        Err(trace) => {
+            analysis.diagnostics.extend(trace.traces().map(diagnostic));
+            return analysis;
+        }
+    };
+
+    let mut scopes = Scopes::default();
+    let mut refs = References::default();
+    let mut imported = vec![];
+    let mut visited = vec![];
+
+    for module in uses.iter() {
+        let AyNode { span, inner } = module;
+        let Some(file) = file else {
+            let kind = ErrorKind::from(ParsingError::ImportWithoutPath {
+                module: inner.name.clone(),
+            });
+            analysis.diagnostics.push(error(
+                span,
+                Stage::AstBuilding,
+                Some(kind.code()),
+                kind.to_string(),
+            ));
+            continue;
+        };
+
+        let path = match parsing::module_path(file, module) {
+            Ok(path) => path,
+            Err(trace) => {
+                analysis.diagnostics.extend(trace.traces().map(diagnostic));
+                continue;
+            }
+        };
+        let res = import(
+            &path,
+            span,
+            &mut scopes,
+            &mut refs,
+            &mut imported,
+            &mut visited,
+        );
+        if let Err(trace) = res {
+            let (code, message) = trace
+                .errors()
+                .next()
+                .map_or_else(Default::default, |(_, err)| {
+                    (err.code(), err.message().to_owned())
+                });
+            analysis.diagnostics.push(error(
+                span,
+                Stage::AstBuilding,
+                code,
+                format!("In `{path}`: {message}"),
+            ));
+        }
+    }
+
+    // Open-ended so that remembered functions survive the document growing
+    let everywhere = 0..usize::MAX;
+    let mut functions = BUILTINS
+        .iter()
+        .map(|name| Function {
+            name: name.to_string(),
+            args: None,
+            scope: everywhere.clone(),
+        })
+        .collect::<Vec<_>>();
+    imported.iter().for_each(|AyNode { inner, .. }| {
+        if let binding::Statement::FunDec(binding::FunDec { name, args, .. }) = inner {
+            functions.push(Function {
+                name: name.clone(),
+                args: Some(args.clone()),
+                scope: everywhere.clone(),
+            });
+        }
+    });
+    declared_functions(&ast, everywhere, &mut functions);
+    analysis.functions = Some(functions);
+
+    refs.file = analysis.file.clone();
+    let bound = binding::resolve_in(&ast, &mut scopes, &mut refs, &mut warnings);
+    if bound.is_ok() {
+        binding::unused(&refs, &mut warnings);
+    }
+    analysis.references = refs.list;
+
+    let mut errors = vec![];
+    match bound {
+        Err(trace) => errors.extend(trace.traces().map(diagnostic)),
+        // Errors of imported modules are already reported on their `sar`
+        Ok(_) if typing::convert(&imported).is_err() => {}
+        Ok(bound) => {
+            let files = [
+                SourceFile {
+                    path: None,
+                    ast: imported,
+                },
+                SourceFile {
+                    path: analysis.file.clone(),
+                    ast: bound,
+                },
+            ];
+            match typing::convert_files_with(&files, &mut warnings) {
+                Ok(typed) => typed.iter().last().into_iter().for_each(|file| {
+                    file.ast
+                        .iter()
+                        .for_each(|node| hovers(node, &mut analysis.hovers, &mut analysis.types))
+                }),
+                Err(trace) => errors.extend(trace.traces().map(diagnostic)),
+            }
+        }
+    }
+
+    // Warnings are only reported for the open document
+    analysis.diagnostics.extend(
+        warnings
+            .list()
+            .iter()
+            .filter(|found| found.file == analysis.file)
+            .map(warning),
+    );
+    analysis.diagnostics.extend(errors);
+
+    analysis
+}
+
+/// Collects the functions declared in `body`, callable until the end of `scope`.
+fn declared_functions(body: &[AyNode<PStatement>], scope: Range<usize>, res: &mut Vec<Function>) {
+    for AyNode { span, inner } in body {
+        match inner {
+            PStatement::FunDec { name, args, body } => {
+                // Starts at the declaration to allow recursion
+                res.push(Function {
+                    name: name.clone(),
+                    args: Some(args.clone()),
+                    scope: span.range().start..scope.end,
+                });
+                declared_functions(body, span.range(), res);
+            }
+            PStatement::If {
+                then, otherwise, ..
+            } => {
+                declared_functions(then, span.range(), res);
+                declared_functions(otherwise, span.range(), res);
+            }
+            PStatement::Loop { body, .. } | PStatement::Test { body, .. } => {
+                declared_functions(body, span.range(), res)
+            }
+            PStatement::VarDec { .. } | PStatement::Expr(_) => {}
+        }
+    }
+}
+
+/// Binds a module and, first, the modules it imports.
+fn import(
+    path: &str,
+    span: &Span,
+    scopes: &mut Scopes,
+    refs: &mut References,
+    imported: &mut Vec<AyNode<binding::Statement>>,
+    visited: &mut Vec<String>,
+) -> Result<(), Trace> {
+    if visited.iter().any(|other| other == path) {
+        return Ok(());
+    }
+    visited.push(path.to_owned());
+
+    let text = std::fs::read_to_string(path).map_err(|err| {
+        Trace::new(
+            Stage::AstBuilding,
+            Error::new(
+                span.clone(),
+                ParsingError::UnreadableModule {
+                    path: path.to_owned(),
+                    reason: err.to_string(),
+                },
+            ),
+        )
+    })?;
+    let (uses, ast) = parsing::parse_module(text.as_ref())?;
+
+    for module in uses.iter() {
+        import(
+            &parsing::module_path(path, module)?,
+            &module.span,
+            scopes,
+            refs,
+            imported,
+            visited,
+        )?;
+    }
+
+    refs.file = Some(path.to_owned());
+    // Warnings are only reported for the open document
+    imported.extend(binding::resolve_in(
+        &ast,
+        scopes,
+        refs,
+        &mut Warnings::default(),
+    )?);
+
+    Ok(())
+}
+
+impl Analysis {
+    /// Name of the document under the cursor
+    pub fn reference_at(&self, offset: usize) -> Option<&Reference> {
+        self.references.iter().find(|reference| {
+            reference.location.file == self.file && contains(&reference.location.span, offset)
+        })
+    }
+
+    /// Every occurrence of the symbol `reference` resolves to, declaration included
+    pub fn references_to<'a>(
+        &'a self,
+        reference: &'a Reference,
+    ) -> impl Iterator<Item = &'a Reference> {
+        self.references.iter().filter(|other| {
+            other.kind == reference.kind
+                && other.declaration == reference.declaration
+                // Builtins all share an empty declaration
+                && (reference.declaration.is_some() || other.name == reference.name)
+        })
+    }
+
+    /// Functions that can be called at `offset`
+    pub fn functions_at(&self, offset: usize) -> impl Iterator<Item = &Function> {
+        self.functions
+            .iter()
+            .flatten()
+            .filter(move |function| function.scope.start <= offset && offset <= function.scope.end)
+    }
+
+    /// Innermost typed node under the cursor
+    pub fn hover_at(&self, offset: usize) -> Option<&str> {
+        self.hovers
+            .iter()
+            .filter(|(range, _)| range.start <= offset && offset <= range.end)
+            .min_by_key(|(range, _)| range.len())
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+fn contains(span: &Span, offset: usize) -> bool {
+    let range = span.range();
+    range.start <= offset && offset <= range.end
+}
+
+/// Collects hover texts, along with the types of declared variables.
+fn hovers(
+    AyNode { span, inner }: &AyNode<Statement>,
+    res: &mut Vec<(Range<usize>, String)>,
+    types: &mut Vec<(Range<usize>, String)>,
+) {
+    let mut word = |name: &str, text: String| {
+        if let Some(span) = span.find_word(name) {
+            res.push((span.range(), text));
+        }
+    };
+
+    match inner {
+        Statement::FunDec(FunDec {
+            name,
+            args,
+            fun_type,
+            body,
+        }) => {
+            word(name, format!("{name}: {fun_type}"));
+
+            if let AyType::Function { args: types, .. } = fun_type {
+                args.iter()
+                    .zip(types)
+                    .for_each(|(arg, ty)| word(arg, format!("{arg}: {ty}")));
+            }
+
+            body.iter().for_each(|node| hovers(node, res, types));
+        }
+        Statement::VarDec(VarDec { names, values }) => {
+            for (name, value) in names.iter().zip(values) {
+                if let Some(name_span) = span.find_word(name) {
+                    res.push((name_span.range(), format!("{name}: {}", value.expr_type)));
+                    types.push((name_span.range(), value.expr_type.to_string()));
+                }
+            }
+
+            values.iter().for_each(|value| expr_hovers(value, res));
+        }
+        Statement::Expr(expr) => expr_hovers(expr, res),
+        Statement::If {
+            cond,
+            then,
+            otherwise,
+        } => {
+            expr_hovers(cond, res);
+            then.iter()
+                .chain(otherwise)
+                .for_each(|node| hovers(node, res, types));
+        }
+        Statement::Loop { cond, body } => {
+            cond.iter().for_each(|cond| expr_hovers(cond, res));
+            body.iter().for_each(|node| hovers(node, res, types));
+        }
+        Statement::Test { body, .. } => body.iter().for_each(|node| hovers(node, res, types)),
+    }
+}
+
+fn expr_hovers(
+    TypedExpr {
+        span,
+        expr_type,
+        inner,
+    }: &TypedExpr,
+    res: &mut Vec<(Range<usize>, String)>,
+) {
+    match inner {
+        Expr::Var(name) => res.push((span.range(), format!("{name}: {expr_type}"))),
+        _ => res.push((span.range(), expr_type.to_string())),
+    }
+
+    match inner {
+        Expr::FunCall {
+            name,
+            fun_type,
+            args,
+            ..
+        } => {
+            if let Some(span) = span.find_word(name) {
+                res.push((span.range(), format!("{name}: {fun_type}")));
+            }
+            args.iter().for_each(|arg| expr_hovers(arg, res));
+        }
+        Expr::Array { items } => items.iter().for_each(|item| expr_hovers(item, res)),
+        Expr::Comparison { left, right, .. } => {
+            expr_hovers(left, res);
+            expr_hovers(right, res);
+        }
+        Expr::Negated(expr) => expr_hovers(expr, res),
+        Expr::Number(_) | Expr::String(_) | Expr::Var(_) | Expr::Error => {}
+    }
+}
+
+/// Converts a pest line/column location, whose columns count characters.
+///
+/// They match the UTF-16 columns of LSP as long as the source has no astral characters.
+pub fn range(location: &LineColLocation) -> lsp_types::Range {
+    let position = |(line, col): (usize, usize)| Position {
+        line: line.saturating_sub(1) as u32,
+        character: col.saturating_sub(1) as u32,
+    };
+
+    match *location {
+        LineColLocation::Pos(pos) => lsp_types::Range {
+            start: position(pos),
+            end: position(pos),
+        },
+        LineColLocation::Span(start, end) => lsp_types::Range {
+            start: position(start),
+            end: position(end),
+        },
+    }
+}
+
+/// LSP position of a byte offset in `text`.
+pub fn position(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Byte offset of an LSP position in `text`.
+pub fn offset(text: &str, Position { line, character }: Position) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(line as usize)
+        .map(str::len)
+        .sum::<usize>();
+
+    let mut units = 0;
+    text[line_start..]
+        .char_indices()
+        .find(|(_, c)| {
+            let found = units >= character as usize || *c == '\n';
+            units += c.len_utf16();
+            found
+        })
+        .map_or(text.len(), |(index, _)| line_start + index)
+}
+
+/// Diagnostic for the deepest error of a trace, its suggestions stored as text edits in `data`.
+fn diagnostic(trace: &Trace) -> Diagnostic {
+    let (stage, err) = trace
+        .errors()
+        .next()
+        .expect("Traces hold at least one error");
+
+    let edits = err
+        .suggestions()
+        .iter()
+        .map(|Suggestion { span, replacement }| {
+            TextEdit::new(range(span.line_col()), replacement.clone())
+        })
+        .collect::<Vec<_>>();
+
+    Diagnostic {
+        range: range(&err.line_col()),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: err
+            .code()
+            .map(|code| NumberOrString::String(code.to_string())),
+        source: Some("aysìnvi".to_owned()),
+        message: std::iter::once(format!("{stage:?}: {}", err.message()))
+            .chain(err.notes().iter().map(|note| format!("note: {note}")))
+            .chain(err.help().iter().map(|help| format!("help: {help}")))
+            .collect::<Vec<String>>()
+            .join("\n"),
+        data: (!edits.is_empty())
+            .then(|| serde_json::to_value(edits).expect("Text edits are valid JSON")),
+        ..Default::default()
+    }
+}
+
+fn warning(warning: &Warning) -> Diagnostic {
+    Diagnostic {
+        range: range(&warning.error.line_col()),
+        severity: Some(match warning.level {
+            Level::Deny => DiagnosticSeverity::ERROR,
+            _ => DiagnosticSeverity::WARNING,
+        }),
+        code: Some(NumberOrString::String(warning.lint.name().to_owned())),
+        source: Some("aysìnvi".to_owned()),
+        message: format!("{:?}: {}", warning.stage, warning.error.message()),
+        tags: matches!(warning.lint, Lint::UnusedVariable | Lint::UnreachableCode)
+            .then(|| vec![DiagnosticTag::UNNECESSARY]),
+        ..Default::default()
+    }
+}
+
+fn error(span: &Span, stage: Stage, code: Option<Code>, message: String) -> Diagnostic {
+    Diagnostic {
+        range: range(span.line_col()),
+        severity: Some(DiagnosticSeverity::ERROR),
+        code: code.map(|code| NumberOrString::String(code.to_string())),
+        source: Some("aysìnvi".to_owned()),
+        message: format!("{stage:?}: {message}"),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,135 @@
+use crate::ast::binding::conjugations;
+
+use super::analysis::{Analysis, Function};
+
+use lsp_types::{CompletionItem, CompletionItemKind, CompletionItemLabelDetails, InsertTextFormat};
+
+/// Keywords after which a new statement starts
+const STATEMENT_STARTS: [&str; 5] = [".", "ulte", "tsakrr", "txokefyaw", "kem si"];
+/// Keywords after which an expression is expected
+const EXPR_STARTS: [&str; 7] = ["alu", "fa", "sì", "ke", "may'", "txo", "vaykrr"];
+
+/// Snippets for the constructs made of several keywords
+const SNIPPETS: [(&str, &str, &str); 3] = [
+    (
+        "ngop 'u",
+        "Variable declaration",
+        "ngop 'u ${1:name} alu ${2:value}",
+    ),
+    (
+        "txo",
+        "If construct",
+        "txo ${1:condition}, tsakrr\n    $0\ntxew",
+    ),
+    ("leyn", "Loop construct", "leyn kem si\n    $0\nftang"),
+];
+
+#[derive(PartialEq, Eq)]
+enum Position {
+    Statement,
+    Expr,
+}
+
+/// Completion items for the cursor at `offset` in `text`.
+pub fn complete(analysis: &Analysis, text: &str, offset: usize) -> Vec<CompletionItem> {
+    let Some(position) = position(&text[..offset]) else {
+        return vec![];
+    };
+
+    let mut items = analysis
+        .functions_at(offset)
+        .flat_map(function_items)
+        .collect::<Vec<_>>();
+
+    if position == Position::Statement {
+        items.extend(
+            SNIPPETS
+                .iter()
+                .map(|(label, description, snippet)| CompletionItem {
+                    label: label.to_string(),
+                    kind: Some(CompletionItemKind::SNIPPET),
+                    detail: Some(description.to_string()),
+                    insert_text: Some(snippet.to_string()),
+                    insert_text_format: Some(InsertTextFormat::SNIPPET),
+                    ..Default::default()
+                }),
+        );
+    }
+
+    items
+}
+
+/// Whether the word being typed starts a statement or an expression, `None` for declared names.
+fn position(before: &str) -> Option<Position> {
+    // Skips the word being typed
+    let before = before.trim_end_matches(|c: char| c.is_alphanumeric() || c == '\'');
+    if !before.is_empty() && !before.ends_with(char::is_whitespace) && !before.ends_with([',', '.'])
+    {
+        return None;
+    }
+
+    let before = before.trim_end();
+    if before.is_empty()
+        || STATEMENT_STARTS
+            .iter()
+            .any(|start| ends_with_word(before, start))
+    {
+        Some(Position::Statement)
+    } else if EXPR_STARTS
+        .iter()
+        .any(|start| ends_with_word(before, start))
+    {
+        Some(Position::Expr)
+    } else {
+        None
+    }
+}
+
+fn ends_with_word(text: &str, word: &str) -> bool {
+    text.strip_suffix(word).is_some_and(|rest| {
+        word == "."
+            || !rest
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_alphanumeric() || c == '\'')
+    })
+}
+
+/// One item per tense the function can be called with.
+fn function_items(Function { name, args, .. }: &Function) -> Vec<CompletionItem> {
+    let arity = args.as_ref().map_or_else(
+        || "variadic".to_owned(),
+        |args| format!("arity {}", args.len()),
+    );
+
+    conjugations(name)
+        .into_iter()
+        .map(|(form, tense)| {
+            let call = match args.as_deref() {
+                Some([]) => format!("{form} si"),
+                Some(args) => format!(
+                    "{form} fa {}",
+                    args.iter()
+                        .enumerate()
+                        .map(|(index, arg)| format!("${{{}:{arg}}}", index + 1))
+                        .collect::<Vec<String>>()
+                        .join(" sì ")
+                ),
+                None => format!("{form} fa $1"),
+            };
+
+            CompletionItem {
+                label: form,
+                label_details: Some(CompletionItemLabelDetails {
+                    detail: None,
+                    description: Some(format!("{tense} tense")),
+                }),
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(format!("{name}, {arity}")),
+                insert_text: Some(call),
+                insert_text_format: Some(InsertTextFormat::SNIPPET),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
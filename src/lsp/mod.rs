@@ -0,0 +1,366 @@
+pub mod analysis;
+pub mod completion;
+pub mod rename;
+pub mod semantic;
+
+use crate::ast::binding::Location;
+
+use analysis::Analysis;
+
+use std::{collections::HashMap, error::Error};
+
+use {
+    lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response},
+    lsp_types::{
+        notification::{
+            DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+            PublishDiagnostics,
+        },
+        request::{
+            CodeActionRequest, Completion, GotoDefinition, HoverRequest, InlayHintRequest,
+            References, Rename, Request as _, SemanticTokensFullRequest,
+        },
+        CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams,
+        CodeActionProviderCapability, CodeActionResponse, CompletionOptions, CompletionParams,
+        CompletionResponse, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+        DidOpenTextDocumentParams, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+        HoverContents, HoverParams, HoverProviderCapability, InlayHint, InlayHintParams,
+        MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ReferenceParams, RenameParams,
+        SemanticTokens, SemanticTokensFullOptions, SemanticTokensOptions, SemanticTokensParams,
+        SemanticTokensResult, SemanticTokensServerCapabilities, ServerCapabilities,
+        TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit,
+        Url, WorkspaceEdit,
+    },
+    serde::{de::DeserializeOwned, Serialize},
+};
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Open document along with what was last computed from it
+struct Document {
+    text: String,
+    analysis: Analysis,
+}
+
+/// Language server speaking JSON-RPC over stdio
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+pub fn run() -> Result<(), BoxError> {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = serde_json::to_value(ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: semantic::legend(),
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..Default::default()
+            },
+        )),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    })?;
+    connection.initialize(capabilities)?;
+
+    Server::default().main_loop(&connection)?;
+
+    // The writer thread only stops once every sender is gone
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> Result<(), BoxError> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+
+                    connection
+                        .sender
+                        .send(Message::Response(self.request(request)))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(diagnostics) = self.notification(notification)? {
+                        connection
+                            .sender
+                            .send(Message::Notification(Notification::new(
+                                PublishDiagnostics::METHOD.to_owned(),
+                                diagnostics,
+                            )))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn request(&self, Request { id, method, params }: Request) -> Response {
+        let res = match method.as_str() {
+            GotoDefinition::METHOD => handle(params, |params: GotoDefinitionParams| {
+                Ok(self.definition(params.text_document_position_params))
+            }),
+            References::METHOD => {
+                handle(
+                    params,
+                    |params: ReferenceParams| Ok(self.references(params)),
+                )
+            }
+            HoverRequest::METHOD => handle(params, |params: HoverParams| {
+                Ok(self.hover(params.text_document_position_params))
+            }),
+            Completion::METHOD => handle(params, |params: CompletionParams| {
+                Ok(self.completion(params.text_document_position))
+            }),
+            CodeActionRequest::METHOD => handle(params, |params: CodeActionParams| {
+                Ok(Some(code_actions(params)))
+            }),
+            Rename::METHOD => handle(params, |params: RenameParams| self.rename(params)),
+            SemanticTokensFullRequest::METHOD => handle(params, |params: SemanticTokensParams| {
+                Ok(self.semantic_tokens(params.text_document.uri))
+            }),
+            InlayHintRequest::METHOD => handle(params, |params: InlayHintParams| {
+                Ok(self.inlay_hints(params))
+            }),
+            _ => Err((
+                ErrorCode::MethodNotFound,
+                format!("Unhandled method: {method}"),
+            )),
+        };
+
+        match res {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    /// Updates the open documents, returning the diagnostics to publish if any changed.
+    fn notification(
+        &mut self,
+        Notification { method, params }: Notification,
+    ) -> Result<Option<PublishDiagnosticsParams>, BoxError> {
+        let (uri, text) = match method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams = serde_json::from_value(params)?;
+                (params.text_document.uri, params.text_document.text)
+            }
+            DidChangeTextDocument::METHOD => {
+                let mut params: DidChangeTextDocumentParams = serde_json::from_value(params)?;
+                // Full synchronization: the last change holds the whole text
+                let Some(change) = params.content_changes.pop() else {
+                    return Ok(None);
+                };
+                (params.text_document.uri, change.text)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams = serde_json::from_value(params)?;
+                self.documents.remove(&params.text_document.uri);
+                return Ok(Some(PublishDiagnosticsParams::new(
+                    params.text_document.uri,
+                    vec![],
+                    None,
+                )));
+            }
+            _ => return Ok(None),
+        };
+
+        let path = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| path.to_str().map(str::to_owned));
+        let mut analysis = analysis::analyse(path.as_deref(), &text);
+
+        // Code being typed rarely parses, so functions are kept from the last version that did
+        if analysis.functions.is_none() {
+            analysis.functions = self
+                .documents
+                .get(&uri)
+                .and_then(|document| document.analysis.functions.clone());
+        }
+
+        let diagnostics = analysis.diagnostics.clone();
+
+        self.documents
+            .insert(uri.clone(), Document { text, analysis });
+
+        Ok(Some(PublishDiagnosticsParams::new(uri, diagnostics, None)))
+    }
+
+    /// Document and byte offset a position points to
+    fn locate(
+        &self,
+        TextDocumentPositionParams {
+            text_document,
+            position,
+        }: &TextDocumentPositionParams,
+    ) -> Option<(&Document, usize)> {
+        let document = self.documents.get(&text_document.uri)?;
+        Some((document, analysis::offset(&document.text, *position)))
+    }
+
+    fn definition(&self, position: TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (document, offset) = self.locate(&position)?;
+        let declaration = document
+            .analysis
+            .reference_at(offset)?
+            .declaration
+            .as_ref()?;
+
+        Some(GotoDefinitionResponse::Scalar(location(
+            declaration,
+            &position.text_document.uri,
+        )?))
+    }
+
+    fn references(&self, params: ReferenceParams) -> Option<Vec<lsp_types::Location>> {
+        let position = params.text_document_position;
+        let (document, offset) = self.locate(&position)?;
+        let reference = document.analysis.reference_at(offset)?;
+
+        Some(
+            document
+                .analysis
+                .references_to(reference)
+                .filter(|other| params.context.include_declaration || !other.is_declaration())
+                .filter_map(|other| location(&other.location, &position.text_document.uri))
+                .collect(),
+        )
+    }
+
+    fn completion(&self, position: TextDocumentPositionParams) -> Option<CompletionResponse> {
+        let (document, offset) = self.locate(&position)?;
+
+        Some(CompletionResponse::Array(completion::complete(
+            &document.analysis,
+            &document.text,
+            offset,
+        )))
+    }
+
+    fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>, String> {
+        let position = params.text_document_position;
+        let Some((document, offset)) = self.locate(&position) else {
+            return Ok(None);
+        };
+        let Some(reference) = document.analysis.reference_at(offset) else {
+            return Ok(None);
+        };
+
+        let mut changes = HashMap::<Url, Vec<TextEdit>>::new();
+        for (name, replacement) in rename::rename(&document.analysis, reference, &params.new_name)?
+        {
+            let Some(location) = location(&name, &position.text_document.uri) else {
+                continue;
+            };
+            changes
+                .entry(location.uri)
+                .or_default()
+                .push(TextEdit::new(location.range, replacement));
+        }
+
+        Ok(Some(WorkspaceEdit::new(changes)))
+    }
+
+    fn semantic_tokens(&self, uri: Url) -> Option<SemanticTokensResult> {
+        let document = self.documents.get(&uri)?;
+
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: semantic::tokens(&document.analysis, &document.text)?,
+        }))
+    }
+
+    fn inlay_hints(&self, params: InlayHintParams) -> Option<Vec<InlayHint>> {
+        let document = self.documents.get(&params.text_document.uri)?;
+
+        Some(semantic::inlay_hints(
+            &document.analysis,
+            &document.text,
+            params.range,
+        ))
+    }
+
+    fn hover(&self, position: TextDocumentPositionParams) -> Option<Hover> {
+        let (document, offset) = self.locate(&position)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```aysinvi\n{}\n```", document.analysis.hover_at(offset)?),
+            }),
+            range: None,
+        })
+    }
+}
+
+/// Quick fixes applying the suggestions stored on diagnostics.
+fn code_actions(params: CodeActionParams) -> CodeActionResponse {
+    let uri = params.text_document.uri;
+
+    params
+        .context
+        .diagnostics
+        .iter()
+        .flat_map(|diagnostic| {
+            let edits: Vec<TextEdit> = diagnostic
+                .data
+                .clone()
+                .and_then(|data| serde_json::from_value(data).ok())
+                .unwrap_or_default();
+
+            edits.into_iter().map(|edit| {
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!("Replace with `{}`", edit.new_text),
+                    kind: Some(CodeActionKind::QUICKFIX),
+                    diagnostics: Some(vec![diagnostic.clone()]),
+                    edit: Some(WorkspaceEdit::new(HashMap::from([(
+                        uri.clone(),
+                        vec![edit],
+                    )]))),
+                    is_preferred: Some(true),
+                    ..Default::default()
+                })
+            })
+        })
+        .collect()
+}
+
+/// Deserializes the parameters of a request and serializes its result, `null` when missing.
+fn handle<P: DeserializeOwned, R: Serialize>(
+    params: serde_json::Value,
+    f: impl FnOnce(P) -> Result<Option<R>, String>,
+) -> Result<serde_json::Value, (ErrorCode, String)> {
+    let params = serde_json::from_value(params)
+        .map_err(|err| (ErrorCode::InvalidParams, err.to_string()))?;
+    let result = f(params).map_err(|message| (ErrorCode::RequestFailed, message))?;
+
+    serde_json::to_value(result).map_err(|err| (ErrorCode::InternalError, err.to_string()))
+}
+
+/// LSP location of a name, in `document` unless it was declared in an imported file.
+fn location(location: &Location, document: &Url) -> Option<lsp_types::Location> {
+    let uri = match &location.file {
+        Some(file) => Url::from_file_path(file).ok()?,
+        None => document.clone(),
+    };
+
+    Some(lsp_types::Location::new(
+        uri,
+        analysis::range(location.span.line_col()),
+    ))
+}
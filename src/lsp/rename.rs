@@ -0,0 +1,61 @@
+use crate::ast::{
+    binding::{conjugations, Location, Reference, SymbolKind},
+    parsing::{AyParser, Rule},
+};
+
+use super::analysis::Analysis;
+
+use pest::{error::LineColLocation, Parser};
+
+/// Replacements renaming the symbol `reference` resolves to, in every file it occurs in.
+///
+/// Calls keep their tense: renaming `t.aron` to `t.ìran` turns `tìyaron` into `tìyìran`.
+pub fn rename(
+    analysis: &Analysis,
+    reference: &Reference,
+    new_name: &str,
+) -> Result<Vec<(Location, String)>, String> {
+    if reference.declaration.is_none() {
+        return Err(format!("Cannot rename builtin '{}'", reference.name));
+    }
+
+    let (rule, kind) = match reference.kind {
+        SymbolKind::Variable | SymbolKind::Parameter => (Rule::ident, "variable"),
+        SymbolKind::Function => (Rule::fun_ident, "function"),
+    };
+    if !AyParser::parse(rule, new_name).is_ok_and(|pairs| pairs.as_str() == new_name) {
+        return Err(format!("'{new_name}' is not a valid {kind} name"));
+    }
+
+    let forms = conjugations(new_name);
+
+    analysis
+        .references_to(reference)
+        .map(|other| {
+            let replacement = match &other.tense {
+                Some(tense) if !other.is_declaration() => forms
+                    .iter()
+                    .find(|(_, form_tense)| form_tense == tense)
+                    .map(|(form, _)| form.clone())
+                    .ok_or_else(|| {
+                        format!(
+                            "'{new_name}' has no infix position but '{}' is called in the {tense} tense at {}",
+                            other.location.span.as_str(),
+                            position(&other.location),
+                        )
+                    })?,
+                _ => new_name.to_owned(),
+            };
+
+            Ok((other.location.clone(), replacement))
+        })
+        .collect()
+}
+
+fn position(Location { file, span }: &Location) -> String {
+    let (line, col) = match span.line_col() {
+        LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+    };
+
+    format!("{}:{line}:{col}", file.as_deref().unwrap_or("<input>"))
+}
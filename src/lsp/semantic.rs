@@ -0,0 +1,193 @@
+use crate::ast::{
+    binding::SymbolKind,
+    cst::{self, CstNode, TokenKind},
+    parsing::Rule,
+};
+
+use super::analysis::{self, Analysis};
+
+use std::ops::Range;
+
+use lsp_types::{
+    InlayHint, InlayHintKind, InlayHintLabel, SemanticToken, SemanticTokenModifier,
+    SemanticTokenType, SemanticTokensLegend,
+};
+
+/// Token types, indexed by `SemanticToken::token_type`
+const TYPES: [SemanticTokenType; 7] = [
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::PARAMETER,
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+];
+
+/// Token modifiers, as bits of `SemanticToken::token_modifiers_bitset`
+const MODIFIERS: [SemanticTokenModifier; 2] = [
+    SemanticTokenModifier::DECLARATION,
+    SemanticTokenModifier::DEFAULT_LIBRARY,
+];
+
+const FUNCTION: u32 = 0;
+const VARIABLE: u32 = 1;
+const PARAMETER: u32 = 2;
+const KEYWORD: u32 = 3;
+const STRING: u32 = 4;
+const NUMBER: u32 = 5;
+const COMMENT: u32 = 6;
+
+const DECLARATION: u32 = 1 << 0;
+const DEFAULT_LIBRARY: u32 = 1 << 1;
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TYPES.to_vec(),
+        token_modifiers: MODIFIERS.to_vec(),
+    }
+}
+
+/// Semantic tokens of `text`, names being classified by what they are bound to.
+///
+/// `None` if the document does not parse.
+pub fn tokens(analysis: &Analysis, text: &str) -> Option<Vec<SemanticToken>> {
+    let cst = cst::parse(text).ok()?;
+
+    let mut tokens = cst
+        .tokens()
+        .filter_map(|token| match token.kind {
+            TokenKind::Keyword => Some((token.span.start()..token.span.end(), KEYWORD, 0)),
+            TokenKind::Comment => Some((token.span.start()..token.span.end(), COMMENT, 0)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    cst.nodes().for_each(|node| literals(node, &mut tokens));
+
+    tokens.extend(
+        analysis
+            .references
+            .iter()
+            .filter(|reference| reference.location.file == analysis.file)
+            .map(|reference| {
+                let token_type = match reference.kind {
+                    SymbolKind::Function => FUNCTION,
+                    SymbolKind::Variable => VARIABLE,
+                    SymbolKind::Parameter => PARAMETER,
+                };
+                let modifiers = if reference.declaration.is_none() {
+                    DEFAULT_LIBRARY
+                } else if reference.is_declaration() {
+                    DECLARATION
+                } else {
+                    0
+                };
+
+                (reference.location.span.range(), token_type, modifiers)
+            }),
+    );
+
+    // Strings win over their `san` and `sìk` keywords
+    tokens.sort_by_key(|(range, _, _)| (range.start, std::cmp::Reverse(range.end)));
+
+    let mut res = vec![];
+    let (mut last_end, mut last_line, mut last_start) = (0, 0, 0);
+
+    for (range, token_type, modifiers) in tokens {
+        if range.start < last_end {
+            continue;
+        }
+        last_end = range.end;
+
+        // Tokens cannot span several lines
+        for line in lines(text, range) {
+            let start = analysis::position(text, line.start);
+
+            res.push(SemanticToken {
+                delta_line: start.line - last_line,
+                delta_start: if start.line == last_line {
+                    start.character - last_start
+                } else {
+                    start.character
+                },
+                length: text[line].encode_utf16().count() as u32,
+                token_type,
+                token_modifiers_bitset: modifiers,
+            });
+
+            (last_line, last_start) = (start.line, start.character);
+        }
+    }
+
+    Some(res)
+}
+
+/// Numbers and strings, `san` and `sìk` included.
+fn literals(node: &CstNode, res: &mut Vec<(Range<usize>, u32, u32)>) {
+    let span = node.pair().as_span();
+
+    match node.rule() {
+        Rule::number => res.push((span.start()..span.end(), NUMBER, 0)),
+        Rule::expr if node.nodes().any(|child| child.rule() == Rule::string) => {
+            res.push((span.start()..span.end(), STRING, 0))
+        }
+        _ => node.nodes().for_each(|child| literals(child, res)),
+    }
+}
+
+/// Splits a range at line breaks, leaving them out.
+fn lines(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut res = vec![];
+    let mut start = range.start;
+
+    for (index, _) in text[range.clone()].match_indices('\n') {
+        res.push(start..range.start + index);
+        start = range.start + index + 1;
+    }
+    res.push(start..range.end);
+
+    res.retain(|line| !line.is_empty());
+    res
+}
+
+/// Tenses of calls to functions with an infix position, and inferred types of declared
+/// variables, within `range`.
+pub fn inlay_hints(analysis: &Analysis, text: &str, range: lsp_types::Range) -> Vec<InlayHint> {
+    let tenses = analysis
+        .references
+        .iter()
+        .filter(|reference| {
+            reference.location.file == analysis.file
+                && reference.name.contains('.')
+                && !reference.is_declaration()
+        })
+        .filter_map(|reference| {
+            Some(InlayHint {
+                position: analysis::position(text, reference.location.span.range().end),
+                label: InlayHintLabel::String(reference.tense.as_ref()?.to_string()),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: Some(true),
+                data: None,
+            })
+        });
+
+    let types = analysis.types.iter().map(|(name, ty)| InlayHint {
+        position: analysis::position(text, name.end),
+        label: InlayHintLabel::String(format!(": {ty}")),
+        kind: Some(InlayHintKind::TYPE),
+        text_edits: None,
+        tooltip: None,
+        padding_left: None,
+        padding_right: None,
+        data: None,
+    });
+
+    tenses
+        .chain(types)
+        .filter(|hint| range.start <= hint.position && hint.position <= range.end)
+        .collect()
+}
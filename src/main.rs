@@ -1,91 +1,495 @@
 #![allow(unused)]
 
-mod ast;
-mod error;
-mod highlight;
-
-extern crate pest;
-#[macro_use]
-extern crate pest_derive;
-
-use crate::{
+use aysinvi::{
     ast::{
-        lib::{AyNode, SourceCode},
+        lib::{AyNode, SourceCode, SourceFile},
         *,
     },
-    error::trace::Trace,
-    highlight::highlight_aysinvi,
+    emit::{self, EmitFormat, EmitStage},
+    error::{
+        code::Code,
+        lint::{Level, Lint, Warnings},
+        locale::{self, Locale},
+        report::{MessageFormat, Reporter},
+        trace::Trace,
+    },
+    fix, formatter,
+    highlight::{self, highlight_aysinvi, render, ColorChoice, Renderer, Theme},
+    interpreter::runtime::Interpreter,
+    lsp, repl, testing,
 };
 
-use pest::{
-    error::{Error, ErrorVariant},
-    iterators::{Pair, Pairs},
-    Parser,
-};
+use std::{collections::BTreeMap, ops::Range, process::ExitCode, time::Instant};
 
-fn main() -> Result<(), Trace> {
-    macro_rules! print_ast {
-        ($ast:ident) => {
-            println!("\x1b[1m{}\x1b[0m", stringify!($ast));
-            match $ast {
-                Ok(ref ast) => println!("{ast:?}"),
-                Err(ref trace) => println!("{trace}"),
-            };
-        };
+use {clap::Parser as _, lsp_types::DiagnosticSeverity};
+
+#[derive(clap::Parser)]
+#[command(version, about, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Source file to run, starts the REPL if omitted
+    file: Option<String>,
+
+    /// Output the result of a compiler stage instead of running the program
+    #[arg(long, value_enum)]
+    emit: Option<EmitStage>,
+
+    /// Format used by `--emit`
+    #[arg(long, value_enum, default_value_t)]
+    emit_format: EmitFormat,
+
+    /// Apply the fixes suggested by errors to the file instead of running it
+    #[arg(long, conflicts_with = "emit")]
+    fix: bool,
+
+    /// How errors are printed on the standard error
+    #[arg(long, value_enum, default_value_t, global = true)]
+    message_format: MessageFormat,
+
+    /// When to color errors and highlighted code, `auto` coloring terminals unless `NO_COLOR` is
+    /// set
+    #[arg(long, value_enum, default_value_t, global = true)]
+    color: ColorChoice,
+
+    /// JSON file mapping highlighting categories and diagnostic roles to styles, overriding the
+    /// default theme
+    #[arg(long, global = true)]
+    theme: Option<String>,
+
+    /// Language of the messages, defaulting to the `AYSINVI_LANG` environment variable, then
+    /// to English
+    #[arg(long, value_enum, global = true)]
+    lang: Option<Locale>,
+
+    /// Do not report a lint
+    #[arg(short = 'A', long, value_enum, global = true)]
+    allow: Vec<Lint>,
+
+    /// Report a lint without failing, the default
+    #[arg(short = 'W', long, value_enum, global = true)]
+    warn: Vec<Lint>,
+
+    /// Fail when a lint is reported
+    #[arg(short = 'D', long, value_enum, global = true)]
+    deny: Vec<Lint>,
+}
+
+impl Cli {
+    /// Level of each lint given on the command line, denying winning over warning over allowing
+    fn levels(&self) -> BTreeMap<Lint, Level> {
+        let levels = [
+            (&self.allow, Level::Allow),
+            (&self.warn, Level::Warn),
+            (&self.deny, Level::Deny),
+        ];
+
+        levels
+            .into_iter()
+            .flat_map(|(lints, level)| lints.iter().map(move |lint| (*lint, level)))
+            .collect()
     }
+}
 
-    let ast = parsing::parse(SourceCode::File("./examples/funargs.ay".to_string()));
-    print_ast!(ast);
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Start an interactive session
+    Repl,
+    /// Format source files in place
+    Fmt {
+        /// Files to format
+        #[arg(required = true)]
+        files: Vec<String>,
 
-    let bound = binding::convert(&ast?);
-    print_ast!(bound);
+        /// Only check that the files are formatted, failing if some are not
+        #[arg(long)]
+        check: bool,
+    },
+    /// Rename a variable or function everywhere it is used, imported modules included
+    Rename {
+        /// File declaring or using the symbol
+        file: String,
+        /// Current name, as declared or in any of its conjugated forms
+        old: String,
+        /// New name, with an infix position for functions called in several tenses
+        new: String,
+    },
+    /// Print a source file with syntax highlighting
+    Highlight {
+        /// File to highlight
+        file: String,
 
-    println!(
-        "{}",
-        highlight_aysinvi(
-            std::fs::read_to_string("./examples/fizzbuzz.ay")
-                .unwrap()
-                .as_ref()
-        )
-    );
+        /// Output format
+        #[arg(long, value_enum, default_value_t)]
+        format: highlight::Format,
+    },
+    /// Print an editor syntax definition generated from the grammar
+    Syntax {
+        #[arg(value_enum)]
+        editor: highlight::editor::Editor,
+    },
+    /// Run the `ngop fmetok` tests of source files, each one on its own
+    Test {
+        /// Files or directories searched recursively for `.ay` files
+        #[arg(default_value = ".")]
+        paths: Vec<String>,
+    },
+    /// Explain an error code, e.g. `AY0102`, with an example program raising it
+    Explain {
+        /// Code printed with the error
+        code: String,
+    },
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Some(lang) = cli.lang {
+        locale::set(lang);
+    }
+    let theme = match cli.theme.as_deref().map(Theme::from_file) {
+        Some(Ok(theme)) => theme,
+        Some(Err(message)) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+        None => Theme::default(),
+    };
+    render::set(Renderer::new(cli.color, &std::io::stderr(), theme.clone()));
+    let mut reporter = Reporter::new(cli.message_format);
+    let levels = cli.levels();
+    let mut warnings = Warnings::new(levels.clone());
+
+    let code = match (cli.command, cli.file) {
+        (Some(Command::Repl), _) | (None, None) => match repl::run() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        },
+        (Some(Command::Fmt { files, check }), _) => fmt(files, check, &mut reporter),
+        (Some(Command::Rename { file, old, new }), _) => rename(file, old, new),
+        (Some(Command::Highlight { file, format }), _) => {
+            // Terminal escape codes follow `--color`, other formats are always styled
+            let renderer = Renderer::new(cli.color, &std::io::stdout(), theme.clone());
+            highlight(file, format, theme, renderer)
+        }
+        (Some(Command::Syntax { editor }), _) => {
+            print!("{}", editor.syntax());
+            ExitCode::SUCCESS
+        }
+        (Some(Command::Test { paths }), _) => test(paths, &levels, &mut reporter),
+        (Some(Command::Explain { code }), _) => match code.parse::<Code>() {
+            Ok(code) => {
+                print!("{}", code.explanation());
+                ExitCode::SUCCESS
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                ExitCode::FAILURE
+            }
+        },
+        (None, Some(file)) if cli.fix => fix(file, &mut reporter),
+        (None, Some(file)) => {
+            let res = run(
+                file.clone(),
+                cli.emit,
+                cli.emit_format,
+                &mut warnings,
+                &mut reporter,
+            );
+
+            match res {
+                Ok(()) if warnings.denied() => ExitCode::FAILURE,
+                Ok(()) => ExitCode::SUCCESS,
+                Err(trace) => {
+                    reporter.report(&trace, Some(file.as_ref()));
+                    ExitCode::FAILURE
+                }
+            }
+        }
+    };
+
+    reporter.finish();
+    code
+}
+
+/// Runs `file` once it is typed, reporting the lints collected in `warnings` beforehand and not
+/// running it if one of them is denied.
+fn run(
+    file: String,
+    emit: Option<EmitStage>,
+    format: EmitFormat,
+    warnings: &mut Warnings,
+    reporter: &mut Reporter,
+) -> Result<(), Trace> {
+    if let Some(stage) = emit {
+        println!("{}", emit::emit(SourceCode::File(file), stage, format)?);
+        return Ok(());
+    }
+
+    let res = parsing::parse_files_with(SourceCode::File(file.clone()), warnings)
+        .and_then(|files| check(&files, warnings));
+    // Printed before running, as the program may never end
+    warnings
+        .list()
+        .iter()
+        .for_each(|warning| reporter.warn(warning, Some(file.as_ref())));
+
+    let bound = res?;
+    if warnings.denied() {
+        return Ok(());
+    }
+    Interpreter::default().run(&bound)?;
 
     Ok(())
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// Binds and types a parsed program, typing it even if it fails to bind so that the errors of
+/// both stages are reported together.
+fn check(
+    files: &[SourceFile<parsing::Statement>],
+    warnings: &mut Warnings,
+) -> Result<Vec<AyNode<binding::Statement>>, Trace> {
+    let (bound, errors) = binding::convert_files_partial(files, warnings);
+    let typed = typing::convert_files_with(&bound, warnings);
+
+    match Trace::collect(errors.into_iter().chain(typed.err())) {
+        Some(trace) => Err(trace),
+        None => Ok(SourceFile::flatten(bound)),
+    }
+}
+
+fn fix(file: String, reporter: &mut Reporter) -> ExitCode {
+    let source = match std::fs::read_to_string(&file) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Cannot read `{file}`: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let fixes = fix::fix_source(Some(file.as_ref()), source.as_ref());
+
+    if fixes.applied > 0 {
+        if let Err(err) = std::fs::write(&file, fixes.source) {
+            eprintln!("Cannot write `{file}`: {err}");
+            return ExitCode::FAILURE;
+        }
+    }
+    eprintln!("Applied {} fix(es) to `{file}`", fixes.applied);
+
+    match fixes.remaining {
+        Some(trace) => {
+            reporter.report(&trace, Some(file.as_ref()));
+            ExitCode::FAILURE
+        }
+        None => ExitCode::SUCCESS,
+    }
+}
+
+fn rename(file: String, old: String, new: String) -> ExitCode {
+    let source = match std::fs::read_to_string(&file) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("Cannot read `{file}`: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let analysis = lsp::analysis::analyse(Some(file.as_ref()), source.as_ref());
+    // Warnings do not keep names from being resolved
+    let error = analysis
+        .diagnostics
+        .iter()
+        .find(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+    if let Some(diagnostic) = error {
+        eprintln!(
+            "{file}:{}:{}: {}",
+            diagnostic.range.start.line + 1,
+            diagnostic.range.start.character + 1,
+            diagnostic.message
+        );
+        return ExitCode::FAILURE;
+    }
+
+    let Some(reference) = analysis.references.iter().find(|reference| {
+        reference.location.file == analysis.file
+            && (reference.name == old || reference.location.span.as_str() == old)
+    }) else {
+        eprintln!("No symbol named '{old}' in `{file}`");
+        return ExitCode::FAILURE;
+    };
+
+    let edits = match lsp::rename::rename(&analysis, reference, new.as_ref()) {
+        Ok(edits) => edits,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // Edits are applied from the end of each file so that earlier ranges stay valid
+    let mut files = BTreeMap::<String, Vec<(Range<usize>, String)>>::new();
+    for (location, replacement) in edits {
+        files
+            .entry(location.file.unwrap_or_else(|| file.clone()))
+            .or_default()
+            .push((location.span.range(), replacement));
+    }
+
+    for (path, mut edits) in files {
+        let res = std::fs::read_to_string(&path).and_then(|mut source| {
+            edits.sort_by_key(|(range, _)| std::cmp::Reverse(range.start));
+            edits
+                .iter()
+                .for_each(|(range, replacement)| source.replace_range(range.clone(), replacement));
+            std::fs::write(&path, source)
+        });
+
+        match res {
+            Ok(()) => eprintln!("Renamed {} occurrence(s) in `{path}`", edits.len()),
+            Err(err) => {
+                eprintln!("Cannot rewrite `{path}`: {err}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn highlight(
+    file: String,
+    format: highlight::Format,
+    theme: Theme,
+    renderer: Renderer,
+) -> ExitCode {
+    match std::fs::read_to_string(&file) {
+        Ok(source) => {
+            match format {
+                highlight::Format::Ansi => print!("{}", renderer.code(&source)),
+                _ => print!("{}", highlight::highlight(source.as_ref(), format, &theme)),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("Cannot read `{file}`: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn fmt(files: Vec<String>, check: bool, reporter: &mut Reporter) -> ExitCode {
+    let mut code = ExitCode::SUCCESS;
+
+    for file in files {
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("Cannot read `{file}`: {err}");
+                code = ExitCode::FAILURE;
+                continue;
+            }
+        };
 
-    const TEST_FOLDER: &str = "./examples/features";
+        match formatter::format_source(source.as_ref()) {
+            Ok(formatted) if formatted == source => {}
+            Ok(_) if check => {
+                eprintln!("`{file}` is not formatted");
+                code = ExitCode::FAILURE;
+            }
+            Ok(formatted) => {
+                if let Err(err) = std::fs::write(&file, formatted) {
+                    eprintln!("Cannot write `{file}`: {err}");
+                    code = ExitCode::FAILURE;
+                }
+            }
+            Err(trace) => {
+                reporter.report(&trace, Some(file.as_ref()));
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
 
-    fn run_tests<F>(path: &str, check: F)
-    where
-        F: Fn(Result<Vec<AyNode<parsing::Statement>>, Trace>) -> bool,
-    {
-        let folder = format!("{TEST_FOLDER}/{path}");
-        let mut entries = std::fs::read_dir(folder.clone())
-            .unwrap_or_else(|err| panic!("{err}: Missing folder `{folder}`"));
+    code
+}
 
-        while let Some(Ok(entry)) = entries.next() {
-            let entry = entry.path().to_str().unwrap().to_string();
-            eprintln!("Running test {entry}");
+/// Runs the tests of every file under `paths`, reporting each one as it ends and the failures
+/// last, along with what they printed.
+fn test(paths: Vec<String>, levels: &BTreeMap<Lint, Level>, reporter: &mut Reporter) -> ExitCode {
+    let start = Instant::now();
+    let (mut passed, mut broken_files) = (0, 0);
+    let mut failures = vec![];
 
-            let res = parsing::parse(SourceCode::File(entry));
-            if let Err(trace) = &res {
-                eprintln!("{trace}");
+    for file in testing::discover(&paths) {
+        let mut warnings = Warnings::new(levels.clone());
+        let res = parsing::parse_files_with(SourceCode::File(file.clone()), &mut warnings)
+            .and_then(|files| check(&files, &mut warnings));
+        warnings
+            .list()
+            .iter()
+            .for_each(|warning| reporter.warn(warning, Some(file.as_ref())));
+
+        let bound = match res {
+            Ok(_) if warnings.denied() => {
+                broken_files += 1;
+                continue;
+            }
+            Ok(bound) => bound,
+            Err(trace) => {
+                reporter.report(&trace, Some(file.as_ref()));
+                broken_files += 1;
+                continue;
             }
+        };
 
-            assert!(check(res));
+        let results = testing::run(&bound);
+        if results.is_empty() {
+            continue;
+        }
+
+        println!("\nrunning {} test(s) from `{file}`", results.len());
+        for result in results {
+            let status = match result.failure {
+                Some(_) => "FAILED",
+                None => "ok",
+            };
+            println!(
+                "test {} ... {status} ({:.2?})",
+                result.name, result.duration
+            );
+
+            match result.failure {
+                Some(_) => failures.push((file.clone(), result)),
+                None => passed += 1,
+            }
         }
     }
 
-    #[test]
-    fn valid_expressions() {
-        run_tests("expressions/valid", |output| output.is_ok());
+    if !failures.is_empty() {
+        println!("\nfailures:");
+    }
+    for (file, result) in &failures {
+        println!("\n---- {} in `{file}` ----", result.name);
+        print!("{}", result.output);
+        if let Some(trace) = &result.failure {
+            reporter.report(trace, Some(file.as_ref()));
+        }
     }
 
-    #[test]
-    fn invalid_expressions() {
-        run_tests("expressions/invalid", |output| output.is_err());
+    let ok = failures.is_empty() && broken_files == 0;
+    println!(
+        "\ntest result: {}. {passed} passed; {} failed; {broken_files} file(s) with errors; \
+         finished in {:.2?}",
+        if ok { "ok" } else { "FAILED" },
+        failures.len(),
+        start.elapsed()
+    );
+
+    match ok {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
     }
 }
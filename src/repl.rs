@@ -0,0 +1,127 @@
+use crate::{
+    ast::{
+        binding::{self, Scopes},
+        lib::SourceCode,
+        parsing,
+        typing::{self, Types},
+    },
+    error::trace::Trace,
+    highlight::highlight_aysinvi,
+    interpreter::{runtime::Interpreter, value::Value},
+};
+
+use std::borrow::Cow;
+
+use rustyline::{
+    error::ReadlineError,
+    highlight::Highlighter,
+    validate::{ValidationContext, ValidationResult, Validator},
+    Completer, Editor, Helper, Hinter,
+};
+
+const PROMPT: &str = "ay> ";
+const HISTORY_FILE: &str = ".aysinvi_history";
+
+#[derive(Helper, Completer, Hinter)]
+struct AyHelper;
+
+impl Highlighter for AyHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight_aysinvi(line))
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize, _forced: bool) -> bool {
+        true
+    }
+}
+
+impl Validator for AyHelper {
+    /// Keeps reading lines until the statement is terminated by a `.`
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        let input = ctx.input().trim_end();
+
+        Ok(if input.is_empty() || input.ends_with('.') {
+            ValidationResult::Valid(None)
+        } else {
+            ValidationResult::Incomplete
+        })
+    }
+}
+
+/// Binding scopes, types and interpreter state shared by every input of a session
+#[derive(Default)]
+pub struct Session {
+    scopes: Scopes,
+    types: Types,
+    interpreter: Interpreter,
+}
+
+impl Session {
+    /// Runs one input, leaving the binding scopes and types untouched if it fails to bind or
+    /// type.
+    pub fn eval(&mut self, input: &str) -> Result<Value, Trace> {
+        let ast = parsing::parse(SourceCode::Content(input.to_owned()))?;
+
+        let mut scopes = self.scopes.clone();
+        let bound = binding::convert_in(&ast, &mut scopes)?;
+        let mut types = self.types.clone();
+        typing::convert_in(&bound, &mut types)?;
+        self.scopes = scopes;
+        self.types = types;
+
+        self.interpreter.run(&bound)
+    }
+}
+
+pub fn run() -> rustyline::Result<()> {
+    let mut editor = Editor::new()?;
+    editor.set_helper(Some(AyHelper));
+
+    let history = std::env::var("HOME")
+        .map(|home| format!("{home}/{HISTORY_FILE}"))
+        .unwrap_or_else(|_| HISTORY_FILE.to_owned());
+    // There is no history yet on first launch
+    let _ = editor.load_history(&history);
+
+    let mut session = Session::default();
+
+    loop {
+        match editor.readline(PROMPT) {
+            Ok(input) if input.trim().is_empty() => {}
+            Ok(input) => {
+                editor.add_history_entry(input.as_str())?;
+
+                match session.eval(&input) {
+                    Ok(Value::Unit) => {}
+                    Ok(value) => println!("{value}"),
+                    Err(trace) => eprintln!("{trace}"),
+                }
+            }
+            Err(ReadlineError::Interrupted) => {}
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err),
+        }
+    }
+
+    editor.save_history(&history)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::{code::Code, kind::ErrorKind};
+
+    #[test]
+    fn test_inputs_are_typed() {
+        let mut session = Session::default();
+
+        assert_eq!(session.eval("ngop 'u a alu 1.").unwrap(), Value::Unit);
+        assert_eq!(
+            session
+                .eval("sung fa a sì san x sìk.")
+                .map_err(|trace| trace.kind().map(ErrorKind::code)),
+            Err(Some(Code::TypeMismatch))
+        );
+        assert_eq!(session.eval("sung fa a sì 2.").unwrap(), Value::Number(3));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{
+    ast::{binding::Statement, lib::AyNode},
+    error::trace::Trace,
+    interpreter::runtime::{Capture, Interpreter},
+};
+
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Outcome of a single `ngop fmetok`
+pub struct TestResult {
+    pub name: String,
+    /// Error that made the test fail, `None` if it passed
+    pub failure: Option<Trace>,
+    /// What the test printed, shown along with its failure
+    pub output: String,
+    pub duration: Duration,
+}
+
+/// Source files under `paths`, directories being searched recursively in name order and hidden
+/// ones skipped.
+pub fn discover(paths: &[String]) -> Vec<String> {
+    paths
+        .iter()
+        .flat_map(|path| sources(Path::new(path), true))
+        .collect()
+}
+
+/// `path` itself if it is a file given on the command line, the sources below it otherwise.
+fn sources(path: &Path, given: bool) -> Vec<String> {
+    let hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+    if !path.is_dir() {
+        return match given || path.extension().is_some_and(|ext| ext == "ay") {
+            true => vec![path.to_string_lossy().into_owned()],
+            false => vec![],
+        };
+    }
+    if hidden && !given {
+        return vec![];
+    }
+
+    let mut entries = std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    entries
+        .iter()
+        .flat_map(|entry| sources(entry, false))
+        .collect()
+}
+
+/// Runs every test of a bound file, each one in a fresh interpreter.
+///
+/// Only the declarations preceding a test are run before it, so that tests cannot see each
+/// other's effects nor the output of the rest of the file.
+pub fn run(ast: &[AyNode<Statement>]) -> Vec<TestResult> {
+    ast.iter()
+        .enumerate()
+        .filter_map(|(index, node)| match &node.inner {
+            Statement::Test { name, body } => Some(run_test(&ast[..index], name, body)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn run_test(before: &[AyNode<Statement>], name: &str, body: &[AyNode<Statement>]) -> TestResult {
+    let capture = Capture::default();
+    let mut interpreter = Interpreter::with_output(Box::new(capture.clone()));
+
+    let start = Instant::now();
+    let failure = before
+        .iter()
+        .filter(|node| matches!(node.inner, Statement::FunDec(_) | Statement::VarDec(_)))
+        .try_for_each(|node| interpreter.run(std::slice::from_ref(node)).map(drop))
+        .and_then(|()| interpreter.run(body))
+        .err();
+
+    TestResult {
+        name: name.to_owned(),
+        failure,
+        output: capture.take(),
+        duration: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        ast::{binding, parsing},
+        error::code::Code,
+    };
+
+    use pest::error::LineColLocation;
+
+    #[test]
+    fn test_isolated_runs() {
+        let source = "ngop 'u a alu 2.\n\
+                      wìntxu fa san never run sìk.\n\
+                      ngop fmetok sunga alu\n\
+                      wìntxu fa a ulte\n\
+                      nìngay fa may' a sì 2 livu teng\n\
+                      txew.\n\
+                      ngop 'u a alu 3.\n\
+                      ngop fmetok lukea alu\n\
+                      nìngay fa may' a sì 2 livu teng\n\
+                      txew.\n";
+        let ast = binding::convert(&parsing::parse_content(None, source).unwrap()).unwrap();
+        let results = run(&ast);
+
+        assert_eq!(
+            results
+                .iter()
+                .map(|result| (result.name.as_str(), result.output.as_str()))
+                .collect::<Vec<_>>(),
+            [("sunga", "2\n"), ("lukea", "")]
+        );
+        assert!(results[0].failure.is_none());
+
+        let failure = results[1].failure.as_ref().unwrap();
+        let (_, err) = failure.errors().next().unwrap();
+        assert_eq!(err.code(), Some(Code::AssertionFailed));
+        // The assertion of the second test, where `a` is 3
+        assert!(matches!(
+            err.line_col(),
+            LineColLocation::Pos((9, 1)) | LineColLocation::Span((9, 1), _)
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+//! Golden-output conformance suite
+//!
+//! Every `.ay` file under `examples/` is compiled and run, each output being compared with the
+//! sibling file named after it, e.g. `fibo.stderr` next to `fibo.ay`. A missing file stands for
+//! an empty output. Programs are expected to fail if and only if they are in an `invalid`
+//! directory, so that a broken example cannot pass for an expected error. After an intended change, regenerate them with:
+//!
+//! ```sh
+//! AYSINVI_BLESS=1 cargo test --test conformance
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+/// Environment variable rewriting the expected outputs instead of checking them
+const BLESS_VAR: &str = "AYSINVI_BLESS";
+const EXAMPLES: &str = "examples";
+/// Stages dumped, in order, each one as far as the program gets
+const STAGES: [&str; 3] = ["ast", "bound", "typed"];
+/// Extensions of the outputs of running the program
+const RUN_OUTPUTS: [&str; 2] = ["stdout", "stderr"];
+
+/// Exit code of a Rust program that panicked
+const PANIC_CODE: i32 = 101;
+
+fn aysinvi(args: &[&str], file: &Path) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .args(["--color", "never", "--lang", "en"])
+        .args(args)
+        .arg(file)
+        .output()
+        .unwrap();
+
+    // A panic would otherwise be blessed as the expected output
+    assert_ne!(
+        output.status.code(),
+        Some(PANIC_CODE),
+        "`aysinvi {}` panicked on {}:\n{}",
+        args.join(" "),
+        file.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn text(bytes: Vec<u8>) -> String {
+    String::from_utf8(bytes).unwrap()
+}
+
+/// Every output of `file` by extension, empty ones included, and whether running it succeeded.
+fn outputs(file: &Path) -> (Vec<(&'static str, String)>, bool) {
+    let mut res = vec![];
+
+    // The dump of each stage until one fails, the error in its place
+    let mut failed = false;
+    for stage in STAGES {
+        let output = match failed {
+            true => String::new(),
+            false => {
+                let output = aysinvi(&["--emit", stage, "--emit-format", "sexpr"], file);
+                failed = !output.status.success();
+                text(match failed {
+                    true => output.stderr,
+                    false => output.stdout,
+                })
+            }
+        };
+        res.push((stage, output));
+    }
+
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = aysinvi(&[], file);
+    res.extend(RUN_OUTPUTS.into_iter().zip([text(stdout), text(stderr)]));
+
+    (res, status.success())
+}
+
+/// Source files under `dir`, sorted so that reports are stable.
+fn sources(dir: &Path) -> Vec<PathBuf> {
+    let mut entries = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    entries
+        .into_iter()
+        .flat_map(|path| match path.is_dir() {
+            true => sources(&path),
+            false if path.extension().is_some_and(|ext| ext == "ay") => vec![path],
+            false => vec![],
+        })
+        .collect()
+}
+
+/// First line on which `expected` and `actual` differ, numbered from 1.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+
+    for nbr in 1.. {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(expected), Some(actual)) if expected == actual => {}
+            (None, None) => break,
+            (expected, actual) => {
+                return format!(
+                    "line {nbr}:\n    expected: {}\n    actual:   {}",
+                    expected.unwrap_or("<end of output>"),
+                    actual.unwrap_or("<end of output>")
+                )
+            }
+        }
+    }
+
+    "trailing whitespace".to_owned()
+}
+
+#[test]
+fn test_examples() {
+    let bless = std::env::var_os(BLESS_VAR).is_some_and(|value| !value.is_empty());
+    let mut failures = vec![];
+
+    for source in sources(Path::new(EXAMPLES)) {
+        let (outputs, succeeded) = outputs(&source);
+        let invalid = source.components().any(|dir| dir.as_os_str() == "invalid");
+        if succeeded == invalid {
+            failures.push(format!(
+                "{}: expected to {}",
+                source.display(),
+                if invalid { "fail" } else { "succeed" }
+            ));
+        }
+
+        for (ext, actual) in outputs {
+            let expected_file = source.with_extension(ext);
+            let expected = std::fs::read_to_string(&expected_file).unwrap_or_default();
+            if expected == actual {
+                continue;
+            }
+
+            if !bless {
+                failures.push(format!(
+                    "{}: {}",
+                    expected_file.display(),
+                    first_difference(&expected, &actual)
+                ));
+            } else if actual.is_empty() {
+                std::fs::remove_file(&expected_file).unwrap();
+            } else {
+                std::fs::write(&expected_file, actual).unwrap();
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} outputs differ from the expected ones, rerun with {BLESS_VAR}=1 if that is \
+         intended:\n\n{}",
+        failures.len(),
+        failures.join("\n\n")
+    );
+}
@@ -0,0 +1,279 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{json, Value};
+
+const SOURCE: &str =
+    "ngop lì'ukìng t.aron fa a alu\n    a\ntxew.\nngop 'u b alu tìyaron fa 1.\nwìntxu fa b sì c.\n";
+
+struct Client {
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            match header.trim().split_once(": ") {
+                Some(("Content-Length", value)) => length = value.parse().unwrap(),
+                None => break,
+                _ => {}
+            }
+        }
+
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Sends a request and skips notifications until its response arrives.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Skips messages until a notification for `method` arrives.
+    fn notification(&mut self, method: &str) -> Value {
+        loop {
+            let message = self.receive();
+            if message["method"] == method {
+                return message["params"].clone();
+            }
+        }
+    }
+}
+
+fn position(line: u32, character: u32) -> Value {
+    json!({
+        "textDocument": { "uri": "file:///tmp/lsp_test.ay" },
+        "position": { "line": line, "character": character },
+    })
+}
+
+fn start() -> (Child, Client) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aysinvi-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let client = Client {
+        stdin: child.stdin.take().unwrap(),
+        stdout: BufReader::new(child.stdout.take().unwrap()),
+        next_id: 0,
+    };
+
+    (child, client)
+}
+
+#[test]
+fn test_lsp_session() {
+    let (mut child, mut client) = start();
+
+    let capabilities = client.request("initialize", json!({ "capabilities": {} }));
+    assert_eq!(capabilities["capabilities"]["definitionProvider"], true);
+    client.notify("initialized", json!({}));
+
+    client.notify(
+        "textDocument/didOpen",
+        json!({ "textDocument": {
+            "uri": "file:///tmp/lsp_test.ay",
+            "languageId": "aysinvi",
+            "version": 1,
+            "text": SOURCE,
+        }}),
+    );
+
+    // `c` is undefined
+    let diagnostics = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(diagnostics["diagnostics"].as_array().unwrap().len(), 1);
+    assert_eq!(diagnostics["diagnostics"][0]["range"]["start"]["line"], 4);
+
+    // Misspelled calls come with a quick fix
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": "file:///tmp/lsp_test.ay", "version": 2 },
+            "contentChanges": [{ "text": SOURCE.replace("tìyaron", "tìyarom") }],
+        }),
+    );
+    let diagnostics = client.notification("textDocument/publishDiagnostics");
+    let actions = client.request(
+        "textDocument/codeAction",
+        json!({
+            "textDocument": { "uri": "file:///tmp/lsp_test.ay" },
+            "range": diagnostics["diagnostics"][0]["range"],
+            "context": { "diagnostics": diagnostics["diagnostics"] },
+        }),
+    );
+    assert_eq!(
+        actions[0]["edit"]["changes"]["file:///tmp/lsp_test.ay"],
+        json!([{
+            "range": { "start": { "line": 3, "character": 14 }, "end": { "line": 3, "character": 21 } },
+            "newText": "tìyaron",
+        }])
+    );
+
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": "file:///tmp/lsp_test.ay", "version": 3 },
+            "contentChanges": [{ "text": SOURCE.replace(" sì c", "") }],
+        }),
+    );
+    let diagnostics = client.notification("textDocument/publishDiagnostics");
+    assert_eq!(diagnostics["diagnostics"], json!([]));
+
+    let tokens = client.request(
+        "textDocument/semanticTokens/full",
+        json!({ "textDocument": { "uri": "file:///tmp/lsp_test.ay" } }),
+    );
+    // `ngop` and `lì'ukìng` are keywords, `t.aron` a declared function, `a` a parameter
+    assert_eq!(
+        tokens["data"].as_array().unwrap()[..20],
+        json!([0, 0, 4, 3, 0, 0, 5, 8, 3, 0, 0, 9, 6, 0, 1, 0, 7, 2, 3, 0])
+            .as_array()
+            .unwrap()[..]
+    );
+    // `wìntxu` is a builtin
+    assert!(tokens["data"].to_string().contains("1,0,6,0,2"));
+    let hints = client.request(
+        "textDocument/inlayHint",
+        json!({
+            "textDocument": { "uri": "file:///tmp/lsp_test.ay" },
+            "range": { "start": { "line": 0, "character": 0 }, "end": { "line": 5, "character": 0 } },
+        }),
+    );
+    assert_eq!(hints[0]["label"], "imminent future");
+    assert_eq!(hints[0]["position"], json!({ "line": 3, "character": 21 }));
+    assert_eq!(hints[1]["label"], ": Number");
+    assert_eq!(hints[1]["position"], json!({ "line": 3, "character": 9 }));
+
+    // `tìyaron` leads to `t.aron`
+    let definition = client.request("textDocument/definition", position(3, 16));
+    assert_eq!(
+        definition["range"],
+        json!({ "start": { "line": 0, "character": 14 }, "end": { "line": 0, "character": 20 } })
+    );
+
+    let mut params = position(0, 15);
+    params["context"] = json!({ "includeDeclaration": true });
+    let references = client.request("textDocument/references", params);
+    assert_eq!(references.as_array().unwrap().len(), 2);
+
+    let mut params = position(3, 16);
+    params["newName"] = json!("t.ìran");
+    let rename = client.request("textDocument/rename", params);
+    let edits = &rename["changes"]["file:///tmp/lsp_test.ay"];
+    assert_eq!(edits[0]["newText"], "t.ìran");
+    assert_eq!(edits[1]["newText"], "tìyìran");
+
+    let hover = client.request("textDocument/hover", position(3, 9));
+    assert_eq!(hover["contents"]["value"], "```aysinvi\nb: Number\n```");
+
+    let hover = client.request("textDocument/hover", position(0, 15));
+    assert_eq!(
+        hover["contents"]["value"],
+        "```aysinvi\nt.aron: (?0) -> ?0\n```"
+    );
+
+    client.request("shutdown", Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_completion() {
+    let (mut child, mut client) = start();
+    client.request("initialize", json!({ "capabilities": {} }));
+    client.notify("initialized", json!({}));
+
+    let open = |client: &mut Client, text: &str| {
+        client.notify(
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": "file:///tmp/lsp_test.ay", "version": 2 },
+                "contentChanges": [{ "text": text }],
+            }),
+        );
+        client.notification("textDocument/publishDiagnostics");
+    };
+    let labels = |items: Value| {
+        items
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["label"].as_str().unwrap().to_owned())
+            .collect::<Vec<String>>()
+    };
+
+    client.notify(
+        "textDocument/didOpen",
+        json!({ "textDocument": {
+            "uri": "file:///tmp/lsp_test.ay",
+            "languageId": "aysinvi",
+            "version": 1,
+            "text": SOURCE,
+        }}),
+    );
+    client.notification("textDocument/publishDiagnostics");
+
+    // Functions are remembered while the line being typed does not parse
+    open(&mut client, &format!("{SOURCE}wìntxu fa t"));
+    let items = client.request("textDocument/completion", position(5, 11));
+    let labels = labels(items.clone());
+    for form in ["taron", "tìyaron", "tayaron", "wìntxu"] {
+        assert!(labels.contains(&form.to_owned()), "missing {form}");
+    }
+    assert!(!labels.contains(&"leyn".to_owned()));
+
+    let item = items
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|item| item["label"] == "tayaron")
+        .unwrap();
+    assert_eq!(item["detail"], "t.aron, arity 1");
+    assert_eq!(item["insertText"], "tayaron fa ${1:a}");
+
+    // Keyword snippets start statements, declared names get nothing
+    open(&mut client, &format!("{SOURCE}l"));
+    let items = client.request("textDocument/completion", position(5, 1));
+    assert!(items
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|item| item["label"] == "leyn"));
+
+    open(&mut client, &format!("{SOURCE}ngop 'u "));
+    let items = client.request("textDocument/completion", position(5, 8));
+    assert_eq!(items, json!([]));
+
+    client.request("shutdown", Value::Null);
+    client.notify("exit", Value::Null);
+    assert!(child.wait().unwrap().success());
+}
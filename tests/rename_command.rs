@@ -0,0 +1,27 @@
+//! `aysinvi rename` on a file with warnings
+
+use std::process::Command;
+
+#[test]
+fn test_rename_despite_warnings() {
+    let file = std::env::temp_dir().join("rename_command_test.ay");
+    // `y` is never read, which is only worth a warning
+    std::fs::write(&file, "ngop 'u x alu 1.\nngop 'u y alu 2.\nwìntxu fa x.\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .arg("rename")
+        .arg(&file)
+        .args(["x", "z"])
+        .output()
+        .unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "ngop 'u z alu 1.\nngop 'u y alu 2.\nwìntxu fa z.\n"
+    );
+}
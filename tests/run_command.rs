@@ -0,0 +1,31 @@
+//! `aysinvi <FILE>` on a program that never ends
+
+use std::{
+    io::{BufRead, BufReader},
+    process::{Command, Stdio},
+};
+
+#[test]
+fn test_warnings_before_running() {
+    let file = std::env::temp_dir().join("run_command_test.ay");
+    std::fs::write(&file, "leyn kem si\n    1\nftang.\nwìntxu fa 2.\n").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .args(["--color", "never", "--lang", "en"])
+        .arg(&file)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // The loop is still running once the warning is printed
+    let mut line = String::new();
+    BufReader::new(child.stderr.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let running = child.try_wait().unwrap().is_none();
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert_eq!(line, "warning[unreachable_code]\n");
+    assert!(running);
+}
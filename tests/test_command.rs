@@ -0,0 +1,39 @@
+//! `aysinvi test` over the example module tree
+
+use std::process::Command;
+
+#[test]
+fn test_module_tree() {
+    let output = Command::new(env!("CARGO_BIN_EXE_aysìnvi"))
+        .args(["test", "examples/features/tests", "--color", "never"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+
+    // Timings change from one run to the other
+    let lines = stdout
+        .lines()
+        .map(
+            |line| match line.find(" (").or_else(|| line.find("; finished")) {
+                Some(end) => &line[..end],
+                None => line,
+            },
+        )
+        .collect::<Vec<_>>();
+
+    assert!(output.status.success(), "{stdout}");
+    assert_eq!(
+        lines,
+        [
+            "",
+            "running 1 test(s) from `examples/features/tests/main.ay`",
+            "test tsìng ... ok",
+            "",
+            "running 2 test(s) from `examples/features/tests/mesung.ay`",
+            "test holpxay ... ok",
+            "test pamrel ... ok",
+            "",
+            "test result: ok. 3 passed; 0 failed; 0 file(s) with errors",
+        ]
+    );
+}